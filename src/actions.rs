@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use winit::event::MouseButton;
+
+use crate::input::{Input, Key, PadAxis, PadButton, MOUSE_BUTTONS};
+use crate::json_file::{self, JsonError};
+
+// Something the player can do, the game asks about these instead of about keys
+// so the keys can be changed (in controls.json or the controls menu)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    // shoot in the space game
+    Fire,
+    // let go of the row in the block game
+    Drop,
+    // play again after a game over
+    Confirm,
+    // back to the title from anywhere
+    Back,
+    // title screen choices
+    PlayBlocks,
+    PlaySpace,
+    Controls,
+    // type in a seed so a run can be played again (or by a friend)
+    Seed,
+    // block game difficulty
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Action {
+    // in the order the controls menu lists them
+    pub const ALL: [Action; 13] = [
+        Action::MoveLeft, Action::MoveRight, Action::Fire, Action::Drop, Action::Confirm, Action::Back,
+        Action::PlayBlocks, Action::PlaySpace, Action::Controls, Action::Seed, Action::Easy, Action::Medium, Action::Hard,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Fire => "Fire",
+            Action::Drop => "Drop",
+            Action::Confirm => "Play again",
+            Action::Back => "Back to title",
+            Action::PlayBlocks => "Play Falling Blocks",
+            Action::PlaySpace => "Play Space Blocks",
+            Action::Controls => "Controls menu",
+            Action::Seed => "Type a seed",
+            Action::Easy => "Easy",
+            Action::Medium => "Intermediate",
+            Action::Hard => "Advanced",
+        }
+    }
+}
+
+// how far a stick has to be pushed before it counts as a button press
+const STICK_PRESS: f32 = 0.5;
+
+// which way a stick is pushed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StickDir {
+    Negative,
+    Positive,
+}
+
+// One key, mouse button, gamepad button or stick direction that triggers an action.
+// In controls.json this looks like {"key": "Space"}, {"mouse": "Left"}, {"pad": "South"}
+// or {"stick": ["LeftStickX", "negative"]}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(Key),
+    Mouse(MouseButton),
+    Pad(PadButton),
+    Stick(PadAxis, StickDir),
+}
+
+impl Binding {
+    // how much it's held, 0 to 1. Only sticks can be partly held
+    fn value(self, input: &Input) -> f32 {
+        match self {
+            Binding::Stick(axis, StickDir::Negative) => (-input.axis(axis)).max(0.0),
+            Binding::Stick(axis, StickDir::Positive) => input.axis(axis).max(0.0),
+            binding => if binding.is_down(input) { 1.0 } else { 0.0 },
+        }
+    }
+
+    fn is_down(self, input: &Input) -> bool {
+        match self {
+            Binding::Key(key) => input.is_key_down(key),
+            Binding::Mouse(button) => input.is_mouse_down(button),
+            Binding::Pad(button) => input.is_pad_down(button),
+            Binding::Stick(..) => self.value(input) >= STICK_PRESS,
+        }
+    }
+
+    fn is_pressed(self, input: &Input) -> bool {
+        match self {
+            Binding::Key(key) => input.is_key_pressed(key),
+            Binding::Mouse(button) => input.is_mouse_pressed(button),
+            Binding::Pad(button) => input.is_pad_pressed(button),
+            Binding::Stick(axis, dir) => {
+                let prev = match dir {
+                    StickDir::Negative => -input.prev_axis(axis),
+                    StickDir::Positive => input.prev_axis(axis),
+                };
+                self.is_down(input) && prev < STICK_PRESS
+            }
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Mouse(MouseButton::Other(n)) => write!(f, "Mouse {}", n),
+            Binding::Mouse(button) => write!(f, "{:?} click", button),
+            Binding::Pad(button) => write!(f, "Pad {:?}", button),
+            Binding::Stick(axis, StickDir::Negative) => write!(f, "{:?}-", axis),
+            Binding::Stick(axis, StickDir::Positive) => write!(f, "{:?}+", axis),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ActionsError {
+    File(JsonError),
+    // bound to a mouse button past input::MOUSE_BUTTONS
+    MouseButton(PathBuf, u16),
+}
+
+impl fmt::Display for ActionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionsError::File(e) => e.fmt(f),
+            ActionsError::MouseButton(path, n) => write!(f, "controls {} use mouse button {}, only {} are supported", path.display(), n, MOUSE_BUTTONS),
+        }
+    }
+}
+
+impl std::error::Error for ActionsError {}
+
+impl From<JsonError> for ActionsError {
+    fn from(e: JsonError) -> ActionsError {
+        ActionsError::File(e)
+    }
+}
+
+// Which keys and buttons do what. Any number of bindings per action
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ActionMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+    // the keys the game always used, plus clicking to fire and drop and a gamepad layout
+    fn default() -> Self {
+        let left = Binding::Stick(PadAxis::LeftStickX, StickDir::Negative);
+        let right = Binding::Stick(PadAxis::LeftStickX, StickDir::Positive);
+        let bindings = BTreeMap::from([
+            (Action::MoveLeft, vec![Binding::Key(Key::Left), left, Binding::Pad(PadButton::DPadLeft)]),
+            (Action::MoveRight, vec![Binding::Key(Key::Right), right, Binding::Pad(PadButton::DPadRight)]),
+            (Action::Fire, vec![Binding::Key(Key::Space), Binding::Mouse(MouseButton::Left), Binding::Pad(PadButton::South)]),
+            (Action::Drop, vec![Binding::Key(Key::Space), Binding::Mouse(MouseButton::Left), Binding::Pad(PadButton::South)]),
+            (Action::Confirm, vec![Binding::Key(Key::C), Binding::Pad(PadButton::Start)]),
+            (Action::Back, vec![Binding::Key(Key::Escape), Binding::Pad(PadButton::Select)]),
+            (Action::PlayBlocks, vec![Binding::Key(Key::A), Binding::Pad(PadButton::West)]),
+            (Action::PlaySpace, vec![Binding::Key(Key::B), Binding::Pad(PadButton::North)]),
+            (Action::Controls, vec![Binding::Key(Key::K)]),
+            (Action::Seed, vec![Binding::Key(Key::S)]),
+            (Action::Easy, vec![Binding::Key(Key::Key1), Binding::Pad(PadButton::DPadLeft)]),
+            (Action::Medium, vec![Binding::Key(Key::Key2), Binding::Pad(PadButton::DPadUp)]),
+            (Action::Hard, vec![Binding::Key(Key::Key3), Binding::Pad(PadButton::DPadRight)]),
+        ]);
+        Self { bindings }
+    }
+}
+
+impl ActionMap {
+    // read controls from a .json file. Actions the file leaves out keep their default keys
+    pub fn load(path: impl AsRef<Path>) -> Result<ActionMap, ActionsError> {
+        let path = path.as_ref();
+        let file: ActionMap = json_file::load(path)?;
+        for binding in file.bindings.values().flatten() {
+            if let Binding::Mouse(MouseButton::Other(n)) = *binding {
+                if !Input::tracks_mouse_button(MouseButton::Other(n)) {
+                    return Err(ActionsError::MouseButton(path.to_owned(), n));
+                }
+            }
+        }
+        let mut actions = ActionMap::default();
+        actions.bindings.extend(file.bindings);
+        Ok(actions)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), JsonError> {
+        json_file::save(path, self)
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map(|b| b.as_slice()).unwrap_or(&[])
+    }
+
+    // replace everything bound to an action with one binding
+    pub fn bind(&mut self, action: Action, binding: Binding) {
+        self.bindings.insert(action, vec![binding]);
+    }
+
+    // held down right now
+    pub fn is_down(&self, input: &Input, action: Action) -> bool {
+        self.bindings(action).iter().any(|b| b.is_down(input))
+    }
+
+    // went down this update
+    pub fn is_pressed(&self, input: &Input, action: Action) -> bool {
+        self.bindings(action).iter().any(|b| b.is_pressed(input))
+    }
+
+    // how much an action is held, 0 to 1. A stick halfway over is 0.5
+    pub fn value(&self, input: &Input, action: Action) -> f32 {
+        self.bindings(action).iter().map(|b| b.value(input)).fold(0.0, f32::max)
+    }
+
+    // key_axis for actions, -1 when negative is held and 1 for positive.
+    // With a stick it's anywhere in between
+    pub fn axis(&self, input: &Input, negative: Action, positive: Action) -> f32 {
+        self.value(input, positive) - self.value(input, negative)
+    }
+
+    // ex. "Space / Left click", for showing the player
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<String> = self.bindings(action).iter().map(|b| b.to_string()).collect();
+        if names.is_empty() {
+            "(none)".to_owned()
+        } else {
+            names.join(" / ")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::InputEvent;
+
+    #[test]
+    fn rejects_mouse_buttons_input_doesnt_track() {
+        let path = std::env::temp_dir().join(format!("controls-{}.json", std::process::id()));
+        std::fs::write(&path, r#"{"fire": [{"mouse": {"Other": 16}}]}"#).unwrap();
+        assert!(matches!(ActionMap::load(&path), Err(ActionsError::MouseButton(_, 16))));
+    }
+
+    #[test]
+    fn extra_mouse_buttons_are_ignored() {
+        let mut input = Input::default();
+        input.apply(InputEvent::Mouse(MouseButton::Other(40), true));
+        let mut actions = ActionMap::default();
+        actions.bind(Action::Fire, Binding::Mouse(MouseButton::Other(40)));
+        assert!(!actions.is_down(&input, Action::Fire));
+    }
+}
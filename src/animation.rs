@@ -0,0 +1,127 @@
+use serde::Deserialize;
+
+use crate::sprite::GPUSprite;
+
+// What a clip does once it gets to its last frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayMode {
+    // back to the first frame
+    Loop,
+    // back down to the first frame, then up again
+    PingPong,
+    // stay on the last frame
+    Once,
+}
+
+// A list of cells on the sprite sheet shown one after the other.
+// These come from the "animations" part of the atlas .json, see SpriteAtlas::clip.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    // UVs of each frame, ready for GPUSprite::from_region
+    pub frames: Vec<[f32;4]>,
+    // seconds each frame is shown
+    pub frame_time: f32,
+    pub mode: PlayMode,
+    // how fast the sprite turns while the clip plays, radians per second
+    pub spin: f32,
+}
+
+impl Animation {
+    // which frame to show this many seconds into the clip
+    pub fn frame_at(&self, time: f32) -> usize {
+        let count = self.frames.len();
+        let step = (time / self.frame_time) as usize;
+        match self.mode {
+            PlayMode::Loop => step % count,
+            PlayMode::PingPong => {
+                if count < 2 {
+                    return 0;
+                }
+                // 0 1 2 3 2 1 0 1 2 3 ...
+                let period = count*2 - 2;
+                let k = step % period;
+                if k < count { k } else { period - k }
+            }
+            PlayMode::Once => step.min(count - 1),
+        }
+    }
+
+    // how long the clip lasts before it starts over (or stops, for Once)
+    pub fn duration(&self) -> f32 {
+        let count = self.frames.len();
+        match self.mode {
+            PlayMode::PingPong if count >= 2 => (count*2 - 2) as f32 * self.frame_time,
+            _ => count as f32 * self.frame_time,
+        }
+    }
+}
+
+// Plays a clip on one sprite in the sprite list
+struct Animator {
+    sprite: usize,
+    clip: Animation,
+    time: f32,
+}
+
+// All the sprites that are animating, updated every step after the screen runs
+#[derive(Default)]
+pub struct Animators {
+    playing: Vec<Animator>,
+}
+
+impl Animators {
+    // start clip on the sprite at this index, from the first frame, replacing whatever it was playing
+    pub fn play(&mut self, sprite: usize, clip: &Animation) {
+        self.stop(sprite);
+        self.playing.push(Animator { sprite, clip: clip.clone(), time: 0.0 });
+    }
+
+    // leave the sprite on whichever frame it's on
+    pub fn stop(&mut self, sprite: usize) {
+        self.playing.retain(|a| a.sprite != sprite);
+    }
+
+    pub fn clear(&mut self) {
+        self.playing.clear();
+    }
+
+    // move every clip along by dt seconds and show its frame on its sprite.
+    // clips played Once are dropped after their last frame
+    pub fn update(&mut self, dt: f32, sprites: &mut [GPUSprite]) {
+        self.playing.retain_mut(|a| {
+            let Some(sprite) = sprites.get_mut(a.sprite) else {
+                return false;
+            };
+            a.time += dt;
+            sprite.from_region = a.clip.frames[a.clip.frame_at(a.time)];
+            sprite.rotation += a.clip.spin * dt;
+            if a.clip.mode == PlayMode::Once {
+                return a.time < a.clip.duration();
+            }
+            // keep the time small so it doesn't lose precision on long runs
+            a.time %= a.clip.duration();
+            true
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spinning_clip_turns_its_sprite() {
+        let clip = Animation { frames: vec![[0.0, 0.0, 0.5, 0.5], [0.5, 0.0, 0.5, 0.5]], frame_time: 0.1, mode: PlayMode::Loop, spin: 3.0 };
+        let mut sprites = [GPUSprite::hidden(), GPUSprite::hidden()];
+        let mut animators = Animators::default();
+        animators.play(1, &clip);
+        for _ in 0..3 {
+            animators.update(0.05, &mut sprites);
+        }
+        assert!((sprites[1].rotation - 0.45).abs() < 1e-5);
+        assert_eq!(sprites[1].from_region, [0.5, 0.0, 0.5, 0.5]);
+        // only the sprite playing it
+        assert_eq!(sprites[0].rotation, 0.0);
+    }
+}
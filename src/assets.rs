@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::atlas::{AtlasError, SpriteAtlas};
+use crate::bitmap_font::BitmapFont;
+
+// the game's files, relative to the asset root
+pub const SPRITE_ATLAS: &str = "content/block-sprites.json";
+pub const FONT: &str = "content/font.json";
+// what the game asks the sprite atlas for by name, a sprite atlas without all of it isn't loaded
+pub const ATLAS_REGIONS: &[&str] = &["bullet"];
+pub const ATLAS_PREFIXES: &[&str] = &["block_"];
+pub const ATLAS_ANIMATIONS: &[&str] = &["target_colors", "ship_flicker"];
+// the player's key bindings, written by the controls menu
+pub const CONTROLS: &str = "controls.json";
+// best scores, written when someone types their initials
+pub const HIGH_SCORES: &str = "high_scores.json";
+
+// Where the content/ folder is. ASSET_ROOT wins if it's set, otherwise the folder the game's exe
+// is in or any folder above it (so `cargo run` finds the repo from target/debug), otherwise the
+// folder the game was started from
+pub fn find_root() -> PathBuf {
+    if let Ok(root) = std::env::var("ASSET_ROOT") {
+        return PathBuf::from(root);
+    }
+    if let Some(exe_dir) = std::env::current_exe().ok().as_deref().and_then(Path::parent) {
+        if let Some(root) = exe_dir.ancestors().find(|dir| dir.join("content").is_dir()) {
+            return root.to_path_buf();
+        }
+    }
+    std::env::current_dir().unwrap_or_default()
+}
+
+// A file the game needs that couldn't be used
+#[derive(Debug, Clone)]
+pub enum AssetError {
+    Missing(PathBuf),
+    // the file is there but something is wrong with it
+    Corrupt(PathBuf, String),
+}
+
+impl AssetError {
+    fn from_image(path: &Path, e: image::ImageError) -> AssetError {
+        match e {
+            image::ImageError::IoError(e) if e.kind() == std::io::ErrorKind::NotFound => AssetError::Missing(path.to_owned()),
+            e => AssetError::Corrupt(path.to_owned(), e.to_string()),
+        }
+    }
+
+    // path is the .json that was being loaded, the error might be about the PNG it points to
+    fn from_atlas(path: &Path, e: AtlasError) -> AssetError {
+        match e {
+            AtlasError::File(e) if e.is_not_found() => AssetError::Missing(e.path().to_owned()),
+            AtlasError::Image(path, e) => AssetError::from_image(&path, e),
+            e => AssetError::Corrupt(path.to_owned(), e.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AssetError::Missing(path) => write!(f, "missing: {}", path.display()),
+            AssetError::Corrupt(path, e) => write!(f, "can't use {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+// Stands for a texture loaded through Assets, cheap to copy around
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextureHandle(usize);
+
+struct TextureEntry {
+    // where it was loaded from, so it can be loaded again. None for the placeholder
+    path: Option<PathBuf>,
+    texture: wgpu::Texture,
+}
+
+// Loads the game's files from the asset root. Textures are kept by handle and loading the same
+// file twice gives back the same handle. Nothing here panics: a file that can't be loaded is
+// written down in errors() and the game gets a stand in (a checkerboard for textures)
+pub struct Assets {
+    pub root: PathBuf,
+    textures: Vec<TextureEntry>,
+    by_path: HashMap<PathBuf, TextureHandle>,
+    errors: Vec<AssetError>,
+}
+
+impl Assets {
+    pub fn new(root: PathBuf) -> Assets {
+        Assets { root, textures: vec![], by_path: HashMap::new(), errors: vec![] }
+    }
+
+    // where a file of the game really is
+    pub fn path(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.root.join(path)
+    }
+
+    // everything that went wrong since the last clear_errors
+    pub fn errors(&self) -> &[AssetError] {
+        &self.errors
+    }
+
+    pub fn clear_errors(&mut self) {
+        self.errors.clear();
+    }
+
+    pub fn atlas(&mut self, path: impl AsRef<Path>) -> Option<SpriteAtlas> {
+        let path = self.path(path);
+        SpriteAtlas::load(&path)
+            .and_then(|atlas| atlas.require(ATLAS_REGIONS, ATLAS_PREFIXES, ATLAS_ANIMATIONS).map(|_| atlas))
+            .map_err(|e| self.errors.push(AssetError::from_atlas(&path, e)))
+            .ok()
+    }
+
+    pub fn font(&mut self, path: impl AsRef<Path>) -> Option<BitmapFont> {
+        let path = self.path(path);
+        BitmapFont::load(&path).map_err(|e| self.errors.push(AssetError::from_atlas(&path, e))).ok()
+    }
+
+    // a PNG uploaded to the GPU, only read from disk the first time it's asked for
+    pub fn texture(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: impl AsRef<Path>) -> TextureHandle {
+        let path = self.path(path);
+        // ./content/x.png and content/x.png are the same file
+        let key = path.canonicalize().unwrap_or_else(|_| path.clone());
+        if let Some(&handle) = self.by_path.get(&key) {
+            return handle;
+        }
+        let texture = self.upload(device, queue, &path);
+        let handle = TextureHandle(self.textures.len());
+        self.textures.push(TextureEntry { path: Some(path), texture });
+        self.by_path.insert(key, handle);
+        handle
+    }
+
+    // the checkerboard, for when there isn't even a path to load a texture from
+    pub fn placeholder(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) -> TextureHandle {
+        let texture = upload_image(device, queue, &placeholder_image(), Some("placeholder"));
+        self.textures.push(TextureEntry { path: None, texture });
+        TextureHandle(self.textures.len() - 1)
+    }
+
+    pub fn get(&self, handle: TextureHandle) -> &wgpu::Texture {
+        &self.textures[handle.0].texture
+    }
+
+    // read every texture from disk again (for hot reloading), handles stay the same
+    pub fn reload_textures(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        for i in 0..self.textures.len() {
+            if let Some(path) = self.textures[i].path.clone() {
+                self.textures[i].texture = self.upload(device, queue, &path);
+            }
+        }
+    }
+
+    fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, path: &Path) -> wgpu::Texture {
+        let label = path.to_string_lossy();
+        match image::open(path) {
+            Ok(img) => upload_image(device, queue, &img.to_rgba8(), Some(&label)),
+            Err(e) => {
+                self.errors.push(AssetError::from_image(path, e));
+                upload_image(device, queue, &placeholder_image(), Some(&label))
+            }
+        }
+    }
+}
+
+// magenta and black squares, hard to miss on screen
+fn placeholder_image() -> image::RgbaImage {
+    image::RgbaImage::from_fn(8, 8, |x, y| {
+        if (x / 4 + y / 4) % 2 == 0 {
+            image::Rgba([255, 0, 255, 255])
+        } else {
+            image::Rgba([0, 0, 0, 255])
+        }
+    })
+}
+
+fn upload_image(device: &wgpu::Device, queue: &wgpu::Queue, img: &image::RgbaImage, label: Option<&str>) -> wgpu::Texture {
+    let (width, height) = img.dimensions();
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label,
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        view_formats: &[],
+    });
+    queue.write_texture(
+        texture.as_image_copy(),
+        img,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: Some(4 * width),
+            rows_per_image: Some(height),
+        },
+        size,
+    );
+    texture
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the game's own atlas with some of it taken out, written somewhere it can be loaded from
+    fn atlas_without(test: &str, remove: impl Fn(&str) -> bool) -> Assets {
+        let repo = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let mut atlas: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(repo.join(SPRITE_ATLAS)).unwrap()).unwrap();
+        atlas["image"] = repo.join("content/block-sprites.png").to_string_lossy().into();
+        atlas["regions"].as_object_mut().unwrap().retain(|name, _| !remove(name));
+        atlas["animations"].as_object_mut().unwrap().retain(|name, _| !remove(name));
+        let dir = std::env::temp_dir().join(format!("{}-{}", test, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("atlas.json"), atlas.to_string()).unwrap();
+        Assets::new(dir)
+    }
+
+    #[test]
+    fn loads_the_games_atlas() {
+        let mut assets = Assets::new(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+        assert!(assets.atlas(SPRITE_ATLAS).is_some());
+        assert!(assets.errors().is_empty());
+    }
+
+    #[test]
+    fn atlas_without_block_regions_is_an_error() {
+        let mut assets = atlas_without("no-blocks", |name| name.starts_with("block_"));
+        assert!(assets.atlas("atlas.json").is_none());
+        assert!(assets.errors()[0].to_string().contains("block_"));
+    }
+
+    #[test]
+    fn atlas_without_an_animation_is_an_error() {
+        let mut assets = atlas_without("no-flicker", |name| name == "ship_flicker");
+        assert!(assets.atlas("atlas.json").is_none());
+        assert!(assets.errors()[0].to_string().contains("ship_flicker"));
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::animation::{Animation, PlayMode};
+use crate::json_file::{self, JsonError};
+
+// A rectangle on the sprite sheet in pixels, 0,0 is the top left of the PNG
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub w: u32,
+    pub h: u32,
+}
+
+// An animation as it's written in the .json, frames are region names
+#[derive(Deserialize)]
+struct AnimationEntry {
+    frames: Vec<String>,
+    // seconds per frame
+    frame_time: f32,
+    mode: PlayMode,
+    // radians per second the sprite turns while it plays, most don't
+    #[serde(default)]
+    spin: f32,
+}
+
+// What the .json file next to a sprite sheet looks like
+#[derive(Deserialize)]
+struct AtlasFile {
+    // the PNG this describes, relative to the .json file
+    image: String,
+    regions: HashMap<String, PixelRect>,
+    #[serde(default)]
+    animations: HashMap<String, AnimationEntry>,
+}
+
+#[derive(Debug)]
+pub enum AtlasError {
+    File(JsonError),
+    Image(PathBuf, image::ImageError),
+    // a region sticks out past the edge of the PNG
+    OutOfBounds(String),
+    UnknownRegion(String),
+    // no region names start with this
+    NoRegionsWithPrefix(String),
+    // an animation with no frames or a frame time that isn't positive
+    BadAnimation(String),
+    UnknownAnimation(String),
+}
+
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AtlasError::File(e) => e.fmt(f),
+            AtlasError::Image(path, e) => write!(f, "couldn't read sprite sheet {}: {}", path.display(), e),
+            AtlasError::OutOfBounds(name) => write!(f, "sprite region \"{}\" goes past the edge of the sprite sheet", name),
+            AtlasError::UnknownRegion(name) => write!(f, "there is no sprite region named \"{}\" in the sprite atlas", name),
+            AtlasError::NoRegionsWithPrefix(prefix) => write!(f, "there are no sprite regions starting with \"{}\" in the sprite atlas", prefix),
+            AtlasError::BadAnimation(name) => write!(f, "animation \"{}\" needs at least one frame and a frame_time above 0", name),
+            AtlasError::UnknownAnimation(name) => write!(f, "there is no animation named \"{}\" in the sprite atlas", name),
+        }
+    }
+}
+
+impl std::error::Error for AtlasError {}
+
+impl From<JsonError> for AtlasError {
+    fn from(e: JsonError) -> AtlasError {
+        AtlasError::File(e)
+    }
+}
+
+// Named regions of a sprite sheet, so sprites can say "ship" instead of [0.75, 0.9, 0.25, 0.1]
+pub struct SpriteAtlas {
+    // the PNG the regions are on
+    pub image_path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    regions: HashMap<String, PixelRect>,
+    animations: HashMap<String, Animation>,
+}
+
+impl SpriteAtlas {
+    // read the .json description, only the size of the PNG is read here, load_texture does the rest
+    pub fn load(path: impl AsRef<Path>) -> Result<SpriteAtlas, AtlasError> {
+        let path = path.as_ref();
+        let file: AtlasFile = json_file::load(path)?;
+        let image_path = path.parent().unwrap_or(Path::new("")).join(&file.image);
+        let (width, height) = image::image_dimensions(&image_path).map_err(|e| AtlasError::Image(image_path.clone(), e))?;
+        for (name, rect) in &file.regions {
+            if rect.x + rect.w > width || rect.y + rect.h > height {
+                return Err(AtlasError::OutOfBounds(name.clone()));
+            }
+        }
+        let mut atlas = SpriteAtlas { image_path, width, height, regions: file.regions, animations: HashMap::new() };
+        // animation frames are looked up now so a typo shows up at startup
+        for (name, entry) in file.animations {
+            if entry.frames.is_empty() || entry.frame_time <= 0.0 {
+                return Err(AtlasError::BadAnimation(name));
+            }
+            let frames = entry.frames.iter().map(|frame| atlas.region(frame)).collect::<Result<Vec<_>, _>>()?;
+            atlas.animations.insert(name, Animation { frames, frame_time: entry.frame_time, mode: entry.mode, spin: entry.spin });
+        }
+        Ok(atlas)
+    }
+
+    // stand in with no regions, for when the real atlas couldn't be loaded
+    pub fn empty() -> SpriteAtlas {
+        SpriteAtlas { image_path: PathBuf::new(), width: 1, height: 1, regions: HashMap::new(), animations: HashMap::new() }
+    }
+
+    // make sure everything a game asks for by name is there, so a bad atlas is caught
+    // when it's loaded instead of when a screen gets to it
+    pub fn require(&self, regions: &[&str], prefixes: &[&str], animations: &[&str]) -> Result<(), AtlasError> {
+        for name in regions {
+            self.region(name)?;
+        }
+        if let Some(prefix) = prefixes.iter().find(|prefix| self.names_with_prefix(prefix).is_empty()) {
+            return Err(AtlasError::NoRegionsWithPrefix(prefix.to_string()));
+        }
+        for name in animations {
+            self.animation(name)?;
+        }
+        Ok(())
+    }
+
+    // region as normalized UVs (u, v, w, h), ready for GPUSprite::from_region
+    pub fn region(&self, name: &str) -> Result<[f32;4], AtlasError> {
+        let rect = self.regions.get(name).ok_or_else(|| AtlasError::UnknownRegion(name.to_owned()))?;
+        Ok([
+            rect.x as f32 / self.width as f32,
+            rect.y as f32 / self.height as f32,
+            rect.w as f32 / self.width as f32,
+            rect.h as f32 / self.height as f32,
+        ])
+    }
+
+    // same as region, for names the game itself uses, a missing one is a bug in the atlas file
+    pub fn uv(&self, name: &str) -> [f32;4] {
+        self.region(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // an animation from the "animations" part of the .json
+    pub fn animation(&self, name: &str) -> Result<&Animation, AtlasError> {
+        self.animations.get(name).ok_or_else(|| AtlasError::UnknownAnimation(name.to_owned()))
+    }
+
+    // same as animation, for clips the game itself uses
+    pub fn clip(&self, name: &str) -> &Animation {
+        self.animation(name).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    // every region name starting with prefix (ex. "block_"), sorted so the order doesn't change between runs
+    pub fn names_with_prefix(&self, prefix: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = self.regions.keys().map(|name| name.as_str()).filter(|name| name.starts_with(prefix)).collect();
+        names.sort();
+        names
+    }
+}
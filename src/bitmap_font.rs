@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::atlas::AtlasError;
+use crate::json_file;
+use crate::hud::{Hud, MeasureText, TextBlock};
+use crate::sprite::{self, GPUSprite};
+use crate::WINDOW_HEIGHT;
+
+// What the .json file next to a bitmap font PNG looks like.
+// The glyphs are laid out in a grid of equal cells, left to right then top to bottom, in the order of `chars`
+#[derive(Deserialize)]
+struct FontFile {
+    // the PNG this describes, relative to the .json file
+    image: String,
+    cell_width: u32,
+    cell_height: u32,
+    columns: u32,
+    chars: String,
+}
+
+// Pixel font on a sprite sheet. Text laid out with it becomes plain GPUSprites, so it looks
+// the same on every machine and can be drawn without a GPU (see raster.rs).
+pub struct BitmapFont {
+    // the PNG the glyphs are on
+    pub image_path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    cell_width: u32,
+    cell_height: u32,
+    columns: u32,
+    // which cell each character is in
+    glyphs: HashMap<char, u32>,
+}
+
+impl BitmapFont {
+    pub fn load(path: impl AsRef<Path>) -> Result<BitmapFont, AtlasError> {
+        let path = path.as_ref();
+        let file: FontFile = json_file::load(path)?;
+        let image_path = path.parent().unwrap_or(Path::new("")).join(&file.image);
+        let (width, height) = image::image_dimensions(&image_path).map_err(|e| AtlasError::Image(image_path.clone(), e))?;
+        let glyphs: HashMap<char, u32> = file.chars.chars().enumerate().map(|(i, c)| (c, i as u32)).collect();
+        // every cell has to be on the PNG
+        let rows = (glyphs.len() as u32).div_ceil(file.columns.max(1));
+        if file.columns == 0 || file.columns * file.cell_width > width || rows * file.cell_height > height {
+            return Err(AtlasError::OutOfBounds(file.image));
+        }
+        Ok(BitmapFont {
+            image_path,
+            width,
+            height,
+            cell_width: file.cell_width,
+            cell_height: file.cell_height,
+            columns: file.columns,
+            glyphs,
+        })
+    }
+
+    // UVs of a character's cell. Pixel fonts often only have capitals, so lowercase falls back
+    // to uppercase, and anything else missing shows up as a ?
+    fn glyph(&self, c: char) -> Option<[f32;4]> {
+        let cell = self.glyphs.get(&c)
+            .or_else(|| self.glyphs.get(&c.to_ascii_uppercase()))
+            .or_else(|| self.glyphs.get(&'?'))?;
+        let x = (cell % self.columns) * self.cell_width;
+        let y = (cell / self.columns) * self.cell_height;
+        Some([
+            x as f32 / self.width as f32,
+            y as f32 / self.height as f32,
+            self.cell_width as f32 / self.width as f32,
+            self.cell_height as f32 / self.height as f32,
+        ])
+    }
+
+    // how many screen pixels one font pixel becomes, whole numbers only so the pixels stay square
+    fn scale(&self, block: &TextBlock) -> f32 {
+        (block.style.size / self.cell_height as f32).round().max(1.0)
+    }
+
+    // width and height of a block once it's laid out
+    pub fn measure(&self, block: &TextBlock) -> [f32;2] {
+        let scale = self.scale(block);
+        let longest = block.text.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+        let lines = block.text.lines().count();
+        [
+            longest as f32 * self.cell_width as f32 * scale,
+            lines as f32 * self.cell_height as f32 * scale,
+        ]
+    }
+
+    // add a sprite per character of the block, anchored, aligned and colored like the glyphon text.
+    // the sprites are in screen space, draw them with a camera at the origin
+    pub fn layout(&self, block: &TextBlock, text_offset: [f32;2], sprites: &mut Vec<GPUSprite>) {
+        let scale = self.scale(block);
+        let glyph_w = self.cell_width as f32 * scale;
+        let glyph_h = self.cell_height as f32 * scale;
+        let [width, height] = self.measure(block);
+        let [left, top] = block.top_left(width, height);
+        let color = block.style.color.map(|c| c as f32 / 255.0);
+        for (row, line) in block.text.lines().enumerate() {
+            let line_w = line.chars().count() as f32 * glyph_w;
+            let x = left + text_offset[0] + (width - line_w) * block.style.align.fraction();
+            // the hud measures y down from the top, sprites measure it up from the bottom
+            let y = WINDOW_HEIGHT - (top + text_offset[1]) - (row as f32 + 1.0) * glyph_h;
+            for (i, c) in line.chars().enumerate() {
+                if c == ' ' {
+                    continue;
+                }
+                let Some(from_region) = self.glyph(c) else {
+                    continue;
+                };
+                sprites.push(GPUSprite {
+                    to_region: [x + i as f32 * glyph_w, y, glyph_w, glyph_h],
+                    from_region,
+                    color,
+                    rotation: 0.0,
+                    depth: sprite::DEPTH_HUD,
+                    pivot: [0.5, 0.5],
+                });
+            }
+        }
+    }
+
+    // every block of a hud, in order
+    pub fn layout_hud(&self, hud: &Hud, text_offset: [f32;2]) -> Vec<GPUSprite> {
+        let mut sprites = vec![];
+        for (_, block) in hud.blocks() {
+            self.layout(block, text_offset, &mut sprites);
+        }
+        sprites
+    }
+}
+
+impl MeasureText for BitmapFont {
+    fn measure(&mut self, block: &TextBlock) -> [f32;2] {
+        BitmapFont::measure(self, block)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets;
+    use crate::hud::{Align, Anchor};
+
+    #[test]
+    fn menu_hit_box_is_as_wide_as_the_glyphs_drawn() {
+        let mut font = BitmapFont::load(Path::new(env!("CARGO_MANIFEST_DIR")).join(assets::FONT)).unwrap();
+        let block = TextBlock::new("Block game", Anchor::Center).offset(0.0, 42.0).align(Align::Center);
+        let text_offset = [-30.0, 5.0];
+        let [x, y, width, height] = block.layout_bounds(&mut font, text_offset);
+        let mut sprites = vec![];
+        font.layout(&block, text_offset, &mut sprites);
+        let left = sprites.iter().map(|s| s.to_region[0]).fold(f32::MAX, f32::min);
+        let right = sprites.iter().map(|s| s.to_region[0] + s.to_region[2]).fold(f32::MIN, f32::max);
+        let top = sprites.iter().map(|s| s.to_region[1] + s.to_region[3]).fold(f32::MIN, f32::max);
+        let bottom = sprites.iter().map(|s| s.to_region[1]).fold(f32::MAX, f32::min);
+        assert_eq!([left, right], [x, x + width]);
+        // the sprites measure y up from the bottom
+        assert_eq!([top, bottom], [WINDOW_HEIGHT - y, WINDOW_HEIGHT - y - height]);
+    }
+}
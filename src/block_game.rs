@@ -0,0 +1,396 @@
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::actions::Action;
+use crate::clock::REFERENCE_TICK_RATE;
+use crate::game_state::{self, GameState};
+use crate::high_scores::{Game, ScoreEntry};
+use crate::hud::{Align, Anchor, FontFamily, Hud, TextBlock};
+use crate::input::Input;
+use crate::menu::Menu;
+use crate::particles::Burst;
+use crate::scene::{Scene, SceneContext, Transition};
+use crate::sprite;
+use crate::tween::{Ease, SpriteField, Tween, TweenTarget};
+use crate::{WINDOW_WIDTH, WINDOW_HEIGHT, SPRITE_SIZE};
+
+// GAME MODE SETTING FROM THE SETUP SCREEN
+// 1: EASY (start with 5, speed is 4)
+// 2: INTERMEDIATE (start with 4, speed is 6)
+// 3: HARD (start with 3, speed is 10)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Intermediate,
+    Advanced,
+}
+
+impl Difficulty {
+    // how many sprite blocks wide the first row is
+    pub fn drop_sprite_blocks(self) -> usize {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Intermediate => 4,
+            Difficulty::Advanced => 3,
+        }
+    }
+    // starting speed of the sliding row
+    pub fn speed(self) -> usize {
+        match self {
+            Difficulty::Easy => 4,
+            Difficulty::Intermediate => 6,
+            Difficulty::Advanced => 10,
+        }
+    }
+}
+
+// Block falling game setup screen
+pub struct BlockSetupScene {
+    menu: Menu,
+}
+
+// what's on the menu and which action clicking it stands for
+const DIFFICULTY_ITEMS: [(&str, Action); 3] = [
+    ("1:EASY", Action::Easy),
+    ("2:INTERMEDIATE", Action::Medium),
+    ("3:ADVANCED", Action::Hard),
+];
+
+impl BlockSetupScene {
+    pub fn new() -> BlockSetupScene {
+        // under the heading, lined up like they used to be in one block
+        BlockSetupScene { menu: Menu::new("menu", &DIFFICULTY_ITEMS.map(|(text, _)| text)).offset(21.0) }
+    }
+}
+
+impl Scene for BlockSetupScene {
+    fn enter(&mut self, ctx: &mut SceneContext) {
+        *ctx.gs = game_state::init_game_state();
+        // reset sprites
+        *ctx.sprites = sprite::hidden_sprites(ctx.gs.drop_sprite_blocks*12);
+        // menu text slides in from the left
+        ctx.tweens.add(Tween::new(TweenTarget::TextX, 0.0, 0.5).from(-WINDOW_WIDTH/2.0).ease(Ease::CubicOut));
+    }
+
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition {
+        let clicked = self.menu.update(ctx, input).map(|i| DIFFICULTY_ITEMS[i].1);
+        let chosen = |action| clicked == Some(action) || ctx.actions.is_down(input, action);
+        let difficulty = if chosen(Action::Easy){
+            Difficulty::Easy
+        }else if chosen(Action::Medium){
+            Difficulty::Intermediate
+        }else if chosen(Action::Hard){
+            Difficulty::Advanced
+        }else{
+            return Transition::None;
+        };
+        Transition::To(Box::new(BlockPlayScene { difficulty }))
+    }
+
+    fn draw(&self, _gs: &GameState, hud: &mut Hud) {
+        hud.set("heading", TextBlock::new("Press a key or click to choose your difficulty level:", Anchor::Center).offset(0.0, -63.0).align(Align::Center));
+        self.menu.draw(hud);
+    }
+}
+
+// how many rows have to be stacked to finish a level, 12 (one screen) for level 1 and taller after that
+pub fn level_rows(level: usize) -> usize {
+    8 + level*4
+}
+
+// how quickly the camera catches up with the top of the tower (bigger is snappier)
+const CAMERA_FOLLOW: f32 = 4.0;
+// keep this many rows of empty space between the top of the tower and the top of the screen
+const CAMERA_HEADROOM: f32 = 5.0;
+// how long a block trimmed off the tower takes to fade out as it falls
+const TRIM_FADE_SECONDS: f32 = 0.6;
+// how fast trimmed blocks tumble, radians per second
+const TRIM_SPIN: f32 = 6.0;
+// overhangs thinner than this many pixels are shaved off without a piece falling
+const MIN_PIECE: f32 = 1.0;
+
+// The block stacking game itself
+pub struct BlockPlayScene {
+    pub difficulty: Difficulty,
+}
+
+impl BlockPlayScene {
+    // y of the sliding row, always at the top of what the camera sees
+    fn spawn_y(ctx: &SceneContext) -> f32 {
+        ctx.camera.screen_pos[1] + WINDOW_HEIGHT - SPRITE_SIZE
+    }
+
+    // how wide the rows are from now on, in pixels
+    fn set_row_width(gs: &mut GameState, width: f32) {
+        gs.row_width = width;
+        gs.drop_sprite_blocks = (width / SPRITE_SIZE).ceil() as usize;
+    }
+
+    // put a new row of blocks at the top of the screen
+    fn spawn_row(ctx: &mut SceneContext) {
+        let y = Self::spawn_y(ctx);
+        let gs = &mut *ctx.gs;
+        // the tower can be as tall as it wants, so get more sprites when we run out
+        if ctx.sprites.len() < gs.sprites_used + gs.drop_sprite_blocks {
+            ctx.sprites.resize(gs.sprites_used + gs.drop_sprite_blocks*12, sprite::GPUSprite::hidden());
+        }
+        // XPOS OF LEFTMOST SPRITE
+        let x_pos = ctx.rng.gen_range(0..WINDOW_WIDTH as usize-gs.row_width.ceil() as usize);
+        // chooe a random color on the sprite sheet for this row that will drop
+        let color = *ctx.atlas.names_with_prefix("block_").choose(ctx.rng).expect("sprite atlas has no block_ regions, Assets::atlas should have caught that");
+        let color_region = ctx.atlas.uv(color);
+        for i in gs.sprites_used..gs.sprites_used + gs.drop_sprite_blocks {
+            let left = x_pos as f32+(((i-gs.sprites_used)*64) as f32);
+            let block = sprite::GPUSprite { to_region: [left, y, SPRITE_SIZE, SPRITE_SIZE], from_region: color_region, ..sprite::GPUSprite::hidden() };
+            // the last block is only as wide as what's left of the row
+            let right = (left + SPRITE_SIZE).min(x_pos as f32 + gs.row_width);
+            ctx.sprites[i] = block.crop_x(left, right);
+        }
+        gs.row_start = gs.sprites_used;
+        gs.sprites_used += gs.drop_sprite_blocks;
+        gs.waiting = true;
+    }
+
+    // a piece of the row that missed the tower, it falls away and fades out instead of landing
+    fn cut_away(ctx: &mut SceneContext, i: usize) {
+        let sprite = &mut ctx.sprites[i];
+        // in front of the tower while it tumbles past it
+        sprite.depth = sprite::DEPTH_EFFECTS;
+        let [x, y, width, height] = sprite.to_region;
+        ctx.particles.burst(Burst::BlockTrimmed, [x + width/2.0, y + height/2.0], sprite.from_region, ctx.rng);
+        ctx.gs.fading.push(i);
+    }
+
+    // space was pressed: drop the row and cut off whatever hangs over the edges of the tower,
+    // right at the edge so the tower narrows by exactly as much as the row missed by
+    fn drop_row(ctx: &mut SceneContext) {
+        let (left_border, right_border) = (ctx.gs.left_border, ctx.gs.right_border);
+        let mut left_edge = WINDOW_WIDTH;
+        let mut right_edge = 0.0;
+        for i in ctx.gs.row_start..ctx.gs.sprites_used {
+            let block = ctx.sprites[i];
+            let [x, _, width, _] = block.to_region;
+            // pieces cut off this drop are already falling
+            if ctx.gs.fading.contains(&i) {
+                continue;
+            }
+            let kept_left = x.max(left_border);
+            let kept_right = (x + width).min(right_border);
+            // missed the tower completely, or too little is left to stand on
+            if kept_right - kept_left < MIN_PIECE {
+                Self::cut_away(ctx, i);
+                continue;
+            }
+            // what hangs over each side gets its own sprite to fall away with
+            for (left, right) in [(x, kept_left), (kept_right, x + width)] {
+                if right - left >= MIN_PIECE {
+                    let piece = ctx.gs.sprites_used;
+                    ctx.gs.sprites_used += 1;
+                    if ctx.sprites.len() <= piece {
+                        ctx.sprites.resize(piece + 1, sprite::GPUSprite::hidden());
+                    }
+                    ctx.sprites[piece] = block.crop_x(left, right);
+                    Self::cut_away(ctx, piece);
+                }
+            }
+            ctx.sprites[i] = block.crop_x(kept_left, kept_right);
+            left_edge = f32::min(left_edge, kept_left);
+            right_edge = f32::max(right_edge, kept_right);
+        }
+        let gs = &mut *ctx.gs;
+        // drop what's left down to the top of the tower, landing with a bounce.
+        // it takes as long as falling at half the sliding speed would
+        let land_y = gs.num_stacked as f32*SPRITE_SIZE;
+        let fall_speed = gs.speed as f32/2.0 * REFERENCE_TICK_RATE;
+        let landing: Vec<usize> = (gs.row_start..gs.sprites_used).filter(|i| !gs.fading.contains(i) && ctx.sprites[*i].to_region[2] > 0.0).collect();
+        for (n, &i) in landing.iter().enumerate() {
+            let duration = (ctx.sprites[i].to_region[1] - land_y).max(0.0) / fall_speed;
+            let mut tween = Tween::new(TweenTarget::Sprite(i, SpriteField::Y), land_y, duration).ease(Ease::BounceOut);
+            // the whole row has landed once the last block has
+            if n == landing.len() - 1{
+                tween = tween.on_done(|gs| {
+                    gs.falling = false;
+                    gs.num_stacked += 1;
+                    gs.score += 1;
+                });
+            }
+            ctx.tweens.add(tween);
+        }
+        // now update the edges of the game state for the next frame, the next row is as wide as what landed
+        if landing.is_empty() {
+            Self::set_row_width(gs, 0.0);
+        }else{
+            gs.left_border = left_edge;
+            gs.right_border = right_edge;
+            Self::set_row_width(gs, right_edge - left_edge);
+        }
+        gs.waiting = false;
+        // if everything got trimmed there's nothing to land, it's game over
+        gs.falling = !landing.is_empty();
+    }
+
+    //ANIMATE BACK AND FORTH
+    fn slide(ctx: &mut SceneContext) {
+        let y = Self::spawn_y(ctx);
+        let gs = &mut *ctx.gs;
+        // direction = true when going left
+        let mut delta = gs.speed as f32 * ctx.dt * REFERENCE_TICK_RATE;
+        if gs.direction{
+            delta = -delta;
+        }
+        for sprite in ctx.sprites[gs.row_start..gs.sprites_used].iter_mut() {
+            let [cur_x, _, width, _] = sprite.to_region;
+            if cur_x + width >= WINDOW_WIDTH - delta{
+                gs.direction = true;
+            }else if cur_x < 0.0 + delta{
+                gs.direction = false
+            }
+            // the row rides along with the camera as it scrolls up
+            sprite.to_region = [cur_x + delta, y, width, SPRITE_SIZE];
+        }
+    }
+
+    // trimmed blocks keep falling past the tower while they fade and tumble, then get hidden
+    fn fade_trimmed(ctx: &mut SceneContext) {
+        let gs = &mut *ctx.gs;
+        let fall_speed = gs.speed as f32 * ctx.dt * REFERENCE_TICK_RATE;
+        let fade = ctx.dt / TRIM_FADE_SECONDS;
+        let tower_middle = (gs.left_border + gs.right_border) / 2.0;
+        let sprites = &mut *ctx.sprites;
+        gs.fading.retain(|&i| {
+            let sprite = &mut sprites[i];
+            sprite.to_region[1] -= fall_speed;
+            // tip away from the tower: counterclockwise off the left side, clockwise off the right
+            if sprite.to_region[0] + sprite.to_region[2]/2.0 < tower_middle{
+                sprite.rotation += TRIM_SPIN * ctx.dt;
+            }else{
+                sprite.rotation -= TRIM_SPIN * ctx.dt;
+            }
+            sprite.color[3] -= fade;
+            if sprite.color[3] > 0.0{
+                return true;
+            }
+            *sprite = sprite::GPUSprite::hidden();
+            false
+        });
+    }
+
+    // smoothly move the camera so the top of the tower stays in view with room above it
+    fn follow_tower(ctx: &mut SceneContext) {
+        let tower_top = ctx.gs.num_stacked as f32*SPRITE_SIZE;
+        let target = (tower_top - (WINDOW_HEIGHT - CAMERA_HEADROOM*SPRITE_SIZE)).max(0.0);
+        let camera_y = &mut ctx.camera.screen_pos[1];
+        *camera_y += (target - *camera_y) * (1.0 - (-CAMERA_FOLLOW * ctx.dt).exp());
+    }
+}
+
+impl Scene for BlockPlayScene {
+    fn enter(&mut self, ctx: &mut SceneContext) {
+        Self::set_row_width(ctx.gs, self.difficulty.drop_sprite_blocks() as f32 * SPRITE_SIZE);
+        ctx.gs.speed = self.difficulty.speed();
+    }
+
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition {
+        Self::follow_tower(ctx);
+        Self::fade_trimmed(ctx);
+        // Do we need to show new sprites?
+        if !ctx.gs.waiting && !ctx.gs.falling{
+            if ctx.gs.num_stacked >= level_rows(ctx.gs.level){
+                // wait for space to be let go so the next level doesn't drop right away
+                if ctx.actions.is_down(input, Action::Drop){
+                    return Transition::None;
+                }
+                // the top row's color for the confetti
+                let confetti = ctx.sprites[ctx.gs.row_start].from_region;
+                let new_level = ctx.gs.level + 1;
+                let new_speed = ctx.gs.speed + 1;
+                let score = ctx.gs.score;
+                // Reset gs variables manually to reduce cross game variable errors
+                *ctx.gs = game_state::init_game_state();
+                ctx.gs.level = new_level;
+                Self::set_row_width(ctx.gs, self.difficulty.drop_sprite_blocks() as f32 * SPRITE_SIZE);
+                ctx.gs.speed = new_speed;
+                ctx.gs.score = score;
+                *ctx.sprites = sprite::hidden_sprites(ctx.gs.drop_sprite_blocks*12);
+                // back down to the ground for the new tower
+                ctx.camera.screen_pos[1] = 0.0;
+                ctx.particles.burst(Burst::LevelComplete, [WINDOW_WIDTH/2.0, 0.0], confetti, ctx.rng);
+            }else if ctx.gs.drop_sprite_blocks == 0{
+                return Transition::To(Box::new(BlockGameOverScene::new()));
+            }
+            Self::spawn_row(ctx);
+        // the falling row is tweened down, wait for it to land
+        }else if ctx.gs.falling{
+        // We are waiting for space to be clicked, and then acting on it
+        }else if ctx.actions.is_down(input, Action::Drop){
+            Self::drop_row(ctx);
+        }else{
+            Self::slide(ctx);
+        }
+        Transition::None
+    }
+
+    fn draw(&self, gs: &GameState, hud: &mut Hud) {
+        hud.set("level", TextBlock::new(format!("Level: {}", gs.level), Anchor::TopLeft).offset(20.0, 20.0).size(24.0));
+        hud.set("width", TextBlock::new(format!("Width: {:.1}", gs.row_width / SPRITE_SIZE), Anchor::Top).offset(0.0, 20.0).size(24.0));
+        hud.set("score", TextBlock::new(format!("Score: {}", gs.score), Anchor::TopRight).offset(-20.0, 20.0).size(24.0));
+        // big banner while the first row of a level is sliding
+        if gs.waiting && gs.num_stacked == 0{
+            hud.set("banner", TextBlock::new(format!("Level: {}", gs.level), Anchor::Center).size(60.0).color([255, 215, 0, 255]));
+        }
+    }
+}
+
+// Block falling game over screen
+pub struct BlockGameOverScene {
+    // asks for initials first if the score made the high score table
+    entry: ScoreEntry,
+}
+
+impl BlockGameOverScene {
+    pub fn new() -> BlockGameOverScene {
+        BlockGameOverScene { entry: ScoreEntry::new(Game::Blocks) }
+    }
+}
+
+impl Scene for BlockGameOverScene {
+    fn enter(&mut self, ctx: &mut SceneContext) {
+        let score = ctx.gs.score;
+        *ctx.gs = game_state::init_game_state();
+        // reset sprites
+        *ctx.sprites = sprite::hidden_sprites(ctx.gs.drop_sprite_blocks*12);
+        // shake the text side to side a few times
+        let shake = |x| Tween::new(TweenTarget::TextX, x, 0.06).ease(Ease::QuadInOut);
+        ctx.tweens.add(shake(20.0).then(shake(-20.0)).then(shake(12.0)).then(shake(-12.0)).then(shake(0.0)));
+        ctx.tweens.add(Tween::new(TweenTarget::Flash, 0.0, 0.5).from(1.0).ease(Ease::QuadOut));
+        self.entry.start(ctx, score);
+    }
+
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition {
+        if self.entry.typing() {
+            self.entry.update(ctx, input);
+            Transition::None
+        }else if ctx.actions.is_down(input, Action::Confirm){
+            Transition::To(Box::new(BlockSetupScene::new()))
+        }else{
+            Transition::None
+        }
+    }
+
+    fn draw(&self, _gs: &GameState, hud: &mut Hud) {
+        hud.set("title", TextBlock::new("GAME OVER!!!", Anchor::Center).offset(0.0, -60.0).size(60.0).color([255, 80, 80, 255]));
+        if !self.entry.typing() {
+            hud.set("menu", TextBlock::new("Press c to continue playing this game\nPress esc for title screen", Anchor::Center).offset(0.0, 50.0).align(Align::Center));
+        }
+        hud.set("scores", TextBlock::new(self.entry.text.as_str(), Anchor::Bottom).offset(0.0, -30.0).size(20.0).family(FontFamily::Monospace).align(Align::Center));
+    }
+
+    // esc skips typing initials instead of leaving
+    fn escapable(&self) -> bool {
+        !self.entry.typing()
+    }
+
+    fn wants_text(&self) -> bool {
+        self.entry.typing()
+    }
+}
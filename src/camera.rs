@@ -0,0 +1,102 @@
+use crate::{WINDOW_WIDTH, WINDOW_HEIGHT};
+
+// camera struct
+// screen_pos is the bottom left corner of what we can see, screen_size is how much of the game world fits on screen
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+#[derive(Debug)]
+pub struct GPUCamera {
+    pub screen_pos: [f32;2],
+    pub screen_size: [f32;2]
+}
+
+impl Default for GPUCamera {
+    // looking at the first screen of the world, bottom left at 0,0
+    fn default() -> Self {
+        Self {
+            screen_pos: [0.0, 0.0],
+            screen_size: [WINDOW_WIDTH, WINDOW_HEIGHT],
+        }
+    }
+}
+
+// How the WINDOW_WIDTH x WINDOW_HEIGHT game picture gets fit into a window of a different size
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalePolicy {
+    // fill the whole window, squashing the picture if the window is a different shape
+    Stretch,
+    // as big as fits without changing the shape, black bars on the sides left over
+    Letterbox,
+    // like Letterbox but only whole number scales (1x, 2x, 3x...) so pixels stay square and sharp
+    IntegerScale,
+}
+
+impl ScalePolicy {
+    // cycle through the policies (for a settings key)
+    pub fn next(self) -> ScalePolicy {
+        match self {
+            ScalePolicy::Stretch => ScalePolicy::Letterbox,
+            ScalePolicy::Letterbox => ScalePolicy::IntegerScale,
+            ScalePolicy::IntegerScale => ScalePolicy::Stretch,
+        }
+    }
+}
+
+// The part of the window the game is drawn into, in physical pixels with 0,0 at the top left of the window
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Viewport {
+    pub fn new(policy: ScalePolicy, window_width: u32, window_height: u32) -> Viewport {
+        let (window_width, window_height) = (window_width.max(1) as f32, window_height.max(1) as f32);
+        let fit = (window_width / WINDOW_WIDTH).min(window_height / WINDOW_HEIGHT);
+        let scale = match policy {
+            ScalePolicy::Stretch => {
+                return Viewport { x: 0.0, y: 0.0, width: window_width, height: window_height };
+            }
+            ScalePolicy::Letterbox => fit,
+            // a window smaller than 1x can't fit any whole scale, so shrink it like Letterbox does
+            ScalePolicy::IntegerScale => if fit >= 1.0 { fit.floor() } else { fit },
+        };
+        let width = WINDOW_WIDTH * scale;
+        let height = WINDOW_HEIGHT * scale;
+        Viewport {
+            // center the picture, rounded so pixels line up with the window
+            x: ((window_width - width) / 2.0).floor(),
+            y: ((window_height - height) / 2.0).floor(),
+            width,
+            height,
+        }
+    }
+
+    // window pixels per game unit across
+    pub fn scale_x(&self) -> f32 {
+        self.width / WINDOW_WIDTH
+    }
+
+    // window pixels per game unit down
+    pub fn scale_y(&self) -> f32 {
+        self.height / WINDOW_HEIGHT
+    }
+
+    // one scale for things that can't be squashed, like text
+    pub fn scale(&self) -> f32 {
+        self.scale_x().min(self.scale_y())
+    }
+
+    // a point measured from the top left of the game picture (how text is placed) to window pixels
+    pub fn to_window(self, left: f32, top: f32) -> (f32, f32) {
+        (self.x + left * self.scale_x(), self.y + top * self.scale_y())
+    }
+
+    // the other way, window pixels (ex. where the mouse is) to the game's pixels with 0,0 at the
+    // bottom left like the camera. Points in the letterbox bars end up off the edges of the game
+    pub fn to_game(self, x: f32, y: f32) -> [f32;2] {
+        [(x - self.x) / self.scale_x(), WINDOW_HEIGHT - (y - self.y) / self.scale_y()]
+    }
+}
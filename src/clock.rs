@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+// The speeds in the game (gs.speed, gs.bullet_speed...) are in pixels per update
+// at this rate, they get scaled by the real tick length so any tick rate plays the same.
+pub const REFERENCE_TICK_RATE: f32 = 60.0;
+
+// never run more than this many updates in one rendered frame, so a long hitch
+// (dragging the window, a breakpoint) doesn't make the game try to catch up forever
+const MAX_TICKS_PER_FRAME: usize = 5;
+
+// Fixed timestep: the game updates a set number of times per second no matter how
+// fast frames get rendered, and the leftover time is used to interpolate sprites.
+pub struct FixedTimestep {
+    tick: Duration,
+    accumulator: Duration,
+    last: Instant,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: f64) -> FixedTimestep {
+        FixedTimestep {
+            tick: Duration::from_secs_f64(1.0 / tick_rate),
+            accumulator: Duration::ZERO,
+            last: Instant::now(),
+        }
+    }
+
+    // tick rate from the TICK_RATE environment variable (ex. TICK_RATE=30), 60 by default
+    pub fn from_env() -> FixedTimestep {
+        let tick_rate = std::env::var("TICK_RATE")
+            .ok()
+            .and_then(|rate| rate.parse::<f64>().ok())
+            .filter(|rate| *rate > 0.0)
+            .unwrap_or(REFERENCE_TICK_RATE as f64);
+        FixedTimestep::new(tick_rate)
+    }
+
+    // updates per second
+    pub fn tick_rate(&self) -> f64 {
+        1.0 / self.tick.as_secs_f64()
+    }
+
+    // seconds of game time that pass in one update
+    pub fn dt(&self) -> f32 {
+        self.tick.as_secs_f32()
+    }
+
+    // how many updates to run for the time that passed since the last call
+    pub fn advance(&mut self) -> usize {
+        let now = Instant::now();
+        let elapsed = now - self.last;
+        self.last = now;
+        self.advance_by(elapsed)
+    }
+
+    // same as advance but with a made up amount of time, handy for stepping by hand
+    pub fn advance_by(&mut self, elapsed: Duration) -> usize {
+        self.accumulator += elapsed;
+        let ticks = (self.accumulator.as_nanos() / self.tick.as_nanos()) as usize;
+        if ticks > MAX_TICKS_PER_FRAME {
+            // the rest of the backlog is dropped, only the part of a tick in progress is kept
+            self.accumulator = Duration::from_nanos((self.accumulator.as_nanos() % self.tick.as_nanos()) as u64);
+            return MAX_TICKS_PER_FRAME;
+        }
+        self.accumulator -= self.tick * ticks as u32;
+        ticks
+    }
+
+    // how far we are between the last update and the next one, from 0 to 1
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_secs_f32() / self.tick.as_secs_f32()).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_whole_ticks_and_keeps_the_rest() {
+        // 20ms ticks
+        let mut timestep = FixedTimestep::new(50.0);
+        assert_eq!(timestep.advance_by(Duration::from_millis(50)), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-3);
+        assert_eq!(timestep.advance_by(Duration::from_millis(10)), 1);
+        assert_eq!(timestep.alpha(), 0.0);
+    }
+
+    #[test]
+    fn a_hitch_drops_the_backlog() {
+        let mut timestep = FixedTimestep::new(50.0);
+        assert_eq!(timestep.advance_by(Duration::from_millis(2005)), MAX_TICKS_PER_FRAME);
+        assert!((timestep.alpha() - 0.25).abs() < 1e-3);
+        // back to normal on the next frame instead of catching up
+        assert_eq!(timestep.advance_by(Duration::from_millis(20)), 1);
+    }
+}
@@ -0,0 +1,79 @@
+use crate::actions::{Action, Binding};
+use crate::game_state::GameState;
+use crate::hud::{Anchor, Hud, TextBlock};
+use crate::input::{Input, Key};
+use crate::scene::{Scene, SceneContext, Transition};
+use crate::title::TitleScene;
+
+// CONTROLS MENU - change which key does what.
+// Moving around this menu always uses the arrow keys, enter and esc, so bad bindings can't lock you out of it
+#[derive(Default)]
+pub struct ControlsScene {
+    // which action is highlighted, an index into Action::ALL
+    selected: usize,
+    // enter was pressed, the next key, click or gamepad button gets bound to the selected action
+    waiting: bool,
+    // what every action is bound to, kept from the last update for draw
+    lines: Vec<String>,
+}
+
+impl ControlsScene {
+    fn refresh(&mut self, ctx: &SceneContext) {
+        self.lines = Action::ALL.iter().enumerate().map(|(i, &action)| {
+            let marker = if i == self.selected { ">" } else { " " };
+            let keys = if i == self.selected && self.waiting {
+                "press a key, click or pad button...".to_owned()
+            } else {
+                ctx.actions.describe(action)
+            };
+            format!("{} {}: {}", marker, action.name(), keys)
+        }).collect();
+    }
+
+    // Back leaves too (ex. from a gamepad), unless it's bound to a key this menu uses for moving around.
+    // Only when it goes down, so the key that was just bound can't leave on the update after
+    fn back_pressed(&self, ctx: &SceneContext, input: &Input) -> bool {
+        let menu_key = [Key::Up, Key::Down, Key::Return].into_iter().any(|key| input.is_key_down(key));
+        ctx.actions.is_pressed(input, Action::Back) && !menu_key
+    }
+}
+
+impl Scene for ControlsScene {
+    fn enter(&mut self, ctx: &mut SceneContext) {
+        ctx.sprites.clear();
+        self.refresh(ctx);
+    }
+
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition {
+        if self.waiting {
+            let binding = input.first_pressed_key().map(Binding::Key)
+                .or_else(|| input.first_pressed_mouse().map(Binding::Mouse))
+                .or_else(|| input.first_pressed_pad().map(Binding::Pad));
+            if let Some(binding) = binding {
+                ctx.actions.bind(Action::ALL[self.selected], binding);
+                self.waiting = false;
+            }
+        } else if input.is_key_pressed(Key::Up) {
+            self.selected = (self.selected + Action::ALL.len() - 1) % Action::ALL.len();
+        } else if input.is_key_pressed(Key::Down) {
+            self.selected = (self.selected + 1) % Action::ALL.len();
+        } else if input.is_key_pressed(Key::Return) {
+            self.waiting = true;
+        } else if input.is_key_pressed(Key::Escape) || self.back_pressed(ctx, input) {
+            return Transition::To(Box::new(TitleScene::new()));
+        }
+        self.refresh(ctx);
+        Transition::None
+    }
+
+    // esc is handled above, so it can be bound while waiting and the keys that move around here win over Back
+    fn escapable(&self) -> bool {
+        false
+    }
+
+    fn draw(&self, _gs: &GameState, hud: &mut Hud) {
+        hud.set("title", TextBlock::new("Controls", Anchor::Top).offset(0.0, 40.0).size(50.0));
+        hud.set("menu", TextBlock::new(self.lines.join("\n"), Anchor::Left).offset(120.0, 20.0).size(24.0));
+        hud.set("help", TextBlock::new("Up/Down to pick, Enter to change, Esc when done", Anchor::Bottom).offset(0.0, -30.0).size(20.0));
+    }
+}
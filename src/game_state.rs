@@ -2,12 +2,8 @@ use crate::{WINDOW_WIDTH, WINDOW_HEIGHT, SPRITE_SIZE};
 
 
 pub struct GameState{
-    // Screen number: 0 = Title, 1 = Block Game, 2 = Black GO, 3 = Space Game, 4 = Space GO
-    pub screen: usize,
     // level
     pub level: usize,
-    // is the game in progress? no -> title screen
-    pub running: bool,
     // is there a block bouncing side to side at top
     pub waiting: bool,
     // false if right, true if left moving animation
@@ -18,37 +14,31 @@ pub struct GameState{
     pub num_stacked: usize,
     // how many sprites have been used in the vec
     pub sprites_used: usize,
+    // index in the vec of the first sprite of the row that is sliding or falling
+    pub row_start: usize,
+    // indices in the vec of blocks that were trimmed off and are fading away
+    pub fading: Vec<usize>,
     // where is the left border for where blocks can stack
     pub left_border: f32,
-    // where is the right border for where blocks can stack
+    // where is the right border for where blocks can stack (the right edge of the top of the tower)
     pub right_border: f32,
-    // how many sprite blocks wide do we drop next
+    // how many sprite blocks wide do we drop next, the last one can be cut short
     pub drop_sprite_blocks: usize,
+    // how wide the next row is in pixels, it narrows as rows get trimmed
+    pub row_width: f32,
     // speed of blocks moving 
     pub speed:usize,
     // space game start
     pub start:bool,
     // checks bullet state
     pub bullet_moving:bool,
-    // checks if 3 bullets on screen
-    // y pos of bullet
-    pub bullet_y:f32,
-    // x pos of bullet
-    pub bullet_x:f32,
-    //2
-    pub bullet2_x:f32,
-    pub bullet2_y:f32,
-    //3
-    pub bullet3_x:f32,
-    pub bullet3_y:f32,
-
-    // keeps track of whether 3 bullet max is met
-    pub three_bullets:bool,
+    // x, y pos of each of the 3 bullets
+    pub bullets: [[f32;2];3],
+    // which way each bullet flies, length 1. Straight up unless it was aimed with the mouse
+    pub bullet_directions: [[f32;2];3],
 
     pub score: usize,
 
-    pub bullet_index : usize,
-
 
 
     // y pos of ship
@@ -62,8 +52,11 @@ pub struct GameState{
     pub target_x:f32,
     pub target_y:f32,
     // bullet speed
-    pub bullet_speed:f32
-    // text for score
+    pub bullet_speed:f32,
+    // seconds left in the space game round
+    pub timer: f32,
+    // seconds left of the target's flash after it gets hit
+    pub target_flash: f32,
 
 }
 impl GameState {
@@ -77,14 +70,10 @@ pub fn init_game_state() -> GameState {
     // any necessary functions
 
     GameState {
-        // Screen number: 0 = Title, 1 = Block Game, 2 = Block Setup, 3 = Black GO, 4 = Space Game, 5 = Space Setup, 6 = Space GO
-        screen : 0,
         // score
         score: 0,
         //level
         level : 1,
-        // is the game in progress? no -> title screen
-        running : true,
         // is there a block bouncing side to side at top
         waiting : false,
         // false if right, true if left moving animation
@@ -95,32 +84,26 @@ pub fn init_game_state() -> GameState {
         num_stacked : 0,
         // how many sprites have been used in the vec
         sprites_used: 0,
+        // first sprite of the current row
+        row_start: 0,
+        // nothing trimmed yet
+        fading: Vec::new(),
         // where is the left border for where blocks can stack
         left_border : 0.0,
         // where is the right border for where blocks can stack
-        right_border : 1080.0,
+        right_border : WINDOW_WIDTH,
         // how many sprite blocks wide do we drop next
         drop_sprite_blocks : 5,
+        row_width: 5.0*SPRITE_SIZE,
         // speed of blocks moving
         speed: 4,
         // start game - initialize space game vars
         start : true,
         // shooting a bullet
         bullet_moving : false,
-        //
-        bullet_index : 0,
-        //
-        three_bullets : false,
-        // bullet cords
-        bullet_y : 0.0,
         // bullet cords
-        bullet_x: 0.0,
-        //2
-        bullet2_y : 0.0,
-        bullet2_x: 0.0,
-        // 3
-        bullet3_x : 0.0,
-        bullet3_y: 0.0,
+        bullets: [[0.0, 0.0]; 3],
+        bullet_directions: [[0.0, 1.0]; 3],
 
         bullet_count :  0,
         // ship x
@@ -130,6 +113,10 @@ pub fn init_game_state() -> GameState {
         target_x: 500.0,
         target_y: WINDOW_HEIGHT-SPRITE_SIZE,
         // bullet speed
-        bullet_speed : 45.0
+        bullet_speed : 45.0,
+        // space round timer, set when the round starts
+        timer: 0.0,
+        // not flashing
+        target_flash: 0.0,
     }
 }
@@ -0,0 +1,110 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+use crate::input::{Input, InputEvent, PadAxis, PadButton};
+use crate::input_source::InputSource;
+
+// sticks never rest exactly at 0, anything closer than this counts as centered
+pub const DEFAULT_DEADZONE: f32 = 0.15;
+
+// Every connected gamepad, through gilrs. They all drive the same buttons and sticks in Input
+pub struct Gamepads {
+    // None when gamepads aren't supported here, then this does nothing
+    gilrs: Option<Gilrs>,
+    pub deadzone: f32,
+}
+
+impl Gamepads {
+    pub fn new() -> Gamepads {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                log::warn!("no gamepads: {}", e);
+                None
+            }
+        };
+        Gamepads { gilrs, deadzone: DEFAULT_DEADZONE }
+    }
+
+    // throw away whatever the gamepads did, for while a replay plays
+    pub fn discard(&mut self) {
+        if let Some(gilrs) = &mut self.gilrs {
+            while gilrs.next_event().is_some() {}
+        }
+    }
+}
+
+impl InputSource for Gamepads {
+    fn poll(&mut self, input: &mut Input) {
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = pad_button(button) {
+                        input.apply(InputEvent::PadButton(button, true));
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = pad_button(button) {
+                        input.apply(InputEvent::PadButton(button, false));
+                    }
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    if let Some(axis) = pad_axis(axis) {
+                        input.apply(InputEvent::PadAxis(axis, deadzone(value, self.deadzone)));
+                    }
+                }
+                // let go of everything so nothing stays held after the pad is unplugged
+                EventType::Disconnected => {
+                    for button in PadButton::ALL {
+                        input.apply(InputEvent::PadButton(button, false));
+                    }
+                    for axis in PadAxis::ALL {
+                        input.apply(InputEvent::PadAxis(axis, 0.0));
+                    }
+                }
+                EventType::Connected => log::info!("gamepad connected"),
+                _ => {}
+            }
+        }
+    }
+}
+
+// 0 inside the deadzone, outside it the rest of the range is stretched back out to 1
+// so a stick can still be pushed only a little
+fn deadzone(value: f32, deadzone: f32) -> f32 {
+    if value.abs() <= deadzone {
+        0.0
+    } else {
+        value.signum() * ((value.abs() - deadzone) / (1.0 - deadzone)).min(1.0)
+    }
+}
+
+fn pad_button(button: Button) -> Option<PadButton> {
+    match button {
+        Button::South => Some(PadButton::South),
+        Button::East => Some(PadButton::East),
+        Button::North => Some(PadButton::North),
+        Button::West => Some(PadButton::West),
+        Button::LeftTrigger => Some(PadButton::LeftBumper),
+        Button::RightTrigger => Some(PadButton::RightBumper),
+        Button::Select => Some(PadButton::Select),
+        Button::Start => Some(PadButton::Start),
+        Button::DPadUp => Some(PadButton::DPadUp),
+        Button::DPadDown => Some(PadButton::DPadDown),
+        Button::DPadLeft => Some(PadButton::DPadLeft),
+        Button::DPadRight => Some(PadButton::DPadRight),
+        _ => None,
+    }
+}
+
+fn pad_axis(axis: Axis) -> Option<PadAxis> {
+    match axis {
+        Axis::LeftStickX => Some(PadAxis::LeftStickX),
+        Axis::LeftStickY => Some(PadAxis::LeftStickY),
+        Axis::RightStickX => Some(PadAxis::RightStickX),
+        Axis::RightStickY => Some(PadAxis::RightStickY),
+        _ => None,
+    }
+}
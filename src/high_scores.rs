@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::input::Input;
+use crate::json_file::{self, JsonError};
+use crate::scene::SceneContext;
+use crate::text_field::{FieldEvent, TextField};
+
+// how many scores each game keeps
+pub const TABLE_SIZE: usize = 5;
+const INITIALS_LEN: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Game {
+    Blocks,
+    Space,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub initials: String,
+    pub score: usize,
+}
+
+// The best few scores of each game, best first
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HighScores {
+    tables: BTreeMap<Game, Vec<HighScore>>,
+}
+
+impl HighScores {
+    pub fn load(path: impl AsRef<Path>) -> Result<HighScores, JsonError> {
+        json_file::load(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), JsonError> {
+        json_file::save(path, self)
+    }
+
+    pub fn table(&self, game: Game) -> &[HighScore] {
+        self.tables.get(&game).map(|t| t.as_slice()).unwrap_or(&[])
+    }
+
+    // whether a score would make it onto the table
+    pub fn qualifies(&self, game: Game, score: usize) -> bool {
+        let table = self.table(game);
+        score > 0 && (table.len() < TABLE_SIZE || table.iter().any(|s| score > s.score))
+    }
+
+    pub fn add(&mut self, game: Game, initials: String, score: usize) {
+        let table = self.tables.entry(game).or_default();
+        // below anyone who got the same score first
+        let i = table.partition_point(|s| s.score >= score);
+        table.insert(i, HighScore { initials, score });
+        table.truncate(TABLE_SIZE);
+    }
+
+    // one line per score, for the hud
+    pub fn describe(&self, game: Game) -> String {
+        let lines: Vec<String> = self.table(game).iter().enumerate()
+            .map(|(i, s)| format!("{}. {:<3} {:>4}", i + 1, s.initials, s.score))
+            .collect();
+        if lines.is_empty() {
+            "No high scores yet".to_owned()
+        } else {
+            format!("High scores\n{}", lines.join("\n"))
+        }
+    }
+}
+
+// The part of a game over screen that asks for initials when the score made the table
+pub struct ScoreEntry {
+    game: Game,
+    score: usize,
+    // Some while the initials are being typed
+    field: Option<TextField>,
+    // the prompt while typing, the table after, kept from the last update for draw
+    pub text: String,
+}
+
+impl ScoreEntry {
+    pub fn new(game: Game) -> ScoreEntry {
+        ScoreEntry { game, score: 0, field: None, text: String::new() }
+    }
+
+    // call from enter with the final score
+    pub fn start(&mut self, ctx: &SceneContext, score: usize) {
+        self.score = score;
+        if ctx.high_scores.qualifies(self.game, score) {
+            self.field = Some(TextField::new(INITIALS_LEN, |c| c.is_ascii_alphanumeric().then(|| c.to_ascii_uppercase())));
+        }
+        self.refresh(ctx, "");
+    }
+
+    pub fn typing(&self) -> bool {
+        self.field.is_some()
+    }
+
+    pub fn update(&mut self, ctx: &mut SceneContext, input: &Input) {
+        if let Some(field) = &mut self.field {
+            match field.update(input) {
+                Some(FieldEvent::Submit) if !field.text.is_empty() => {
+                    ctx.high_scores.add(self.game, field.text.clone(), self.score);
+                    self.field = None;
+                }
+                // esc skips putting the score in
+                Some(FieldEvent::Cancel) => self.field = None,
+                _ => {}
+            }
+        }
+        self.refresh(ctx, input.preedit());
+    }
+
+    fn refresh(&mut self, ctx: &SceneContext, preedit: &str) {
+        self.text = match &self.field {
+            Some(field) => format!("New high score!\nType your initials: {}\nPress enter when done", field.display(preedit)),
+            None => ctx.high_scores.describe(self.game),
+        };
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// how often the watched files are looked at, checking every frame would hit the disk way too much
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Watches files and folders for changes while developing (run with --dev).
+// Just compares modified times every so often, so it works the same everywhere without extra crates.
+pub struct Watcher {
+    // files, or folders to look through for files
+    roots: Vec<PathBuf>,
+    // every file found last time and when it was last changed
+    seen: HashMap<PathBuf, SystemTime>,
+    last_poll: Instant,
+}
+
+impl Watcher {
+    pub fn new(roots: &[&Path]) -> Watcher {
+        let roots: Vec<PathBuf> = roots.iter().map(|root| root.to_path_buf()).collect();
+        let seen = Self::scan(&roots);
+        Watcher { roots, seen, last_poll: Instant::now() }
+    }
+
+    fn scan(roots: &[PathBuf]) -> HashMap<PathBuf, SystemTime> {
+        let mut seen = HashMap::new();
+        let mut todo = roots.to_vec();
+        while let Some(path) = todo.pop() {
+            if path.is_dir() {
+                if let Ok(entries) = std::fs::read_dir(&path) {
+                    todo.extend(entries.flatten().map(|entry| entry.path()));
+                }
+            } else if let Ok(modified) = std::fs::metadata(&path).and_then(|m| m.modified()) {
+                seen.insert(path, modified);
+            }
+        }
+        seen
+    }
+
+    // files that were added or changed since the last call, empty if it isn't time to look again yet
+    pub fn changed(&mut self) -> Vec<PathBuf> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return vec![];
+        }
+        self.last_poll = Instant::now();
+        let now = Self::scan(&self.roots);
+        let changed = now.iter()
+            .filter(|(path, modified)| self.seen.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.seen = now;
+        changed
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{WINDOW_WIDTH, WINDOW_HEIGHT};
+
+// Which point of the screen a text block hangs off, and which point of the block sits there
+// (ex. TopRight puts the block's top right corner in the screen's top right corner)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    #[cfg_attr(not(test), allow(dead_code))]
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // how far across and down the screen (and the block) this anchor is, 0 to 1
+    pub fn fraction(self) -> [f32;2] {
+        match self {
+            Anchor::TopLeft => [0.0, 0.0],
+            Anchor::Top => [0.5, 0.0],
+            Anchor::TopRight => [1.0, 0.0],
+            Anchor::Left => [0.0, 0.5],
+            Anchor::Center => [0.5, 0.5],
+            Anchor::Right => [1.0, 0.5],
+            Anchor::BottomLeft => [0.0, 1.0],
+            Anchor::Bottom => [0.5, 1.0],
+            Anchor::BottomRight => [1.0, 1.0],
+        }
+    }
+}
+
+// How the lines of a block line up with each other
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    // how much of the space left over on a line goes before it
+    pub fn fraction(self) -> f32 {
+        match self {
+            Align::Left => 0.0,
+            Align::Center => 0.5,
+            Align::Right => 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FontFamily {
+    Serif,
+    // lines numbers up in columns, like the high score tables
+    Monospace,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    // font size in pixels at the game's resolution
+    pub size: f32,
+    // r, g, b, a
+    pub color: [u8;4],
+    pub family: FontFamily,
+    pub align: Align,
+}
+
+impl Default for TextStyle {
+    // what all the text looked like before there was a HUD
+    fn default() -> TextStyle {
+        TextStyle { size: 30.0, color: [255, 255, 255, 255], family: FontFamily::Serif, align: Align::Left }
+    }
+}
+
+impl TextStyle {
+    // space from one line to the next
+    pub fn line_height(&self) -> f32 {
+        self.size * 1.4
+    }
+}
+
+// One piece of text on screen
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextBlock {
+    pub text: String,
+    pub anchor: Anchor,
+    // moves the block away from its anchor, in pixels at the game's resolution (y goes down)
+    pub offset: [f32;2],
+    pub style: TextStyle,
+}
+
+impl TextBlock {
+    pub fn new(text: impl Into<String>, anchor: Anchor) -> TextBlock {
+        TextBlock { text: text.into(), anchor, offset: [0.0, 0.0], style: TextStyle::default() }
+    }
+
+    pub fn offset(mut self, x: f32, y: f32) -> TextBlock {
+        self.offset = [x, y];
+        self
+    }
+
+    pub fn size(mut self, size: f32) -> TextBlock {
+        self.style.size = size;
+        self
+    }
+
+    pub fn color(mut self, color: [u8;4]) -> TextBlock {
+        self.style.color = color;
+        self
+    }
+
+    pub fn family(mut self, family: FontFamily) -> TextBlock {
+        self.style.family = family;
+        self
+    }
+
+    pub fn align(mut self, align: Align) -> TextBlock {
+        self.style.align = align;
+        self
+    }
+
+    // top left corner of the block, given how big it turned out to be
+    pub fn top_left(&self, width: f32, height: f32) -> [f32;2] {
+        let [fx, fy] = self.anchor.fraction();
+        [
+            WINDOW_WIDTH * fx - width * fx + self.offset[0],
+            WINDOW_HEIGHT * fy - height * fy + self.offset[1],
+        ]
+    }
+
+    // where the block ends up as (x, y, width, height) with y going down, when drawn in the given font
+    pub fn layout_bounds(&self, font: &mut dyn MeasureText, text_offset: [f32;2]) -> [f32;4] {
+        let [width, height] = font.measure(self);
+        let [left, top] = self.top_left(width, height);
+        [left + text_offset[0], top + text_offset[1], width, height]
+    }
+}
+
+// A font that can say how big a block comes out without drawing it, so what the mouse is over
+// matches what's on screen. BitmapFont measures its grid, glyphon's FontSystem shapes the text
+pub trait MeasureText {
+    // width and height of the block
+    fn measure(&mut self, block: &TextBlock) -> [f32;2];
+}
+
+// All the text a screen wants shown this frame, by name so each block keeps its own
+// spot (and its own text buffers when it's drawn) from frame to frame.
+#[derive(Clone, Debug, Default)]
+pub struct Hud {
+    blocks: Vec<(String, TextBlock)>,
+}
+
+impl Hud {
+    // add a block, or replace the one with the same name
+    pub fn set(&mut self, name: &str, block: TextBlock) {
+        match self.blocks.iter_mut().find(|(n, _)| n == name) {
+            Some((_, b)) => *b = block,
+            None => self.blocks.push((name.to_owned(), block)),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TextBlock> {
+        self.blocks.iter().find(|(n, _)| n == name).map(|(_, b)| b)
+    }
+
+    // blocks in the order they were set, later ones are drawn on top
+    pub fn blocks(&self) -> impl Iterator<Item = (&str, &TextBlock)> {
+        self.blocks.iter().map(|(n, b)| (n.as_str(), b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn right_anchors_line_up_with_the_right_edge() {
+        let right = TextBlock::new("x", Anchor::Right).top_left(100.0, 40.0);
+        assert_eq!(right, [WINDOW_WIDTH - 100.0, (WINDOW_HEIGHT - 40.0) / 2.0]);
+        let corner = TextBlock::new("x", Anchor::BottomRight).offset(-20.0, -20.0).top_left(100.0, 40.0);
+        assert_eq!(corner, [WINDOW_WIDTH - 120.0, WINDOW_HEIGHT - 60.0]);
+    }
+
+    #[test]
+    fn align_right_pushes_short_lines_to_the_right() {
+        assert_eq!(Align::Left.fraction(), 0.0);
+        assert_eq!(Align::Center.fraction(), 0.5);
+        assert_eq!(Align::Right.fraction(), 1.0);
+    }
+}
@@ -1,6 +1,68 @@
 pub use winit::dpi::PhysicalPosition as MousePos;
 pub use winit::event::VirtualKeyCode as Key;
-use winit::event::{ElementState, MouseButton};
+use serde::{Deserialize, Serialize};
+use winit::event::MouseButton;
+
+// Gamepad buttons, named by where they are (South is A on an xbox pad, X on a playstation one)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl PadButton {
+    pub const ALL: [PadButton; 12] = [
+        PadButton::South, PadButton::East, PadButton::North, PadButton::West,
+        PadButton::LeftBumper, PadButton::RightBumper, PadButton::Select, PadButton::Start,
+        PadButton::DPadUp, PadButton::DPadDown, PadButton::DPadLeft, PadButton::DPadRight,
+    ];
+}
+
+// Analog sticks, -1 to 1. X is positive to the right and Y is positive up
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+}
+
+impl PadAxis {
+    pub const ALL: [PadAxis; 4] = [PadAxis::LeftStickX, PadAxis::LeftStickY, PadAxis::RightStickX, PadAxis::RightStickY];
+}
+
+// how many mouse buttons are kept track of, any others are ignored
+pub const MOUSE_BUTTONS: usize = 16;
+
+// One change to the keyboard, mouse or a gamepad. Everything that changes Input goes through one of these,
+// so a list of them per update is enough to play a run back exactly (see replay.rs)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputEvent {
+    // key and whether it's down
+    Key(Key, bool),
+    Mouse(MouseButton, bool),
+    MouseMove(f64, f64),
+    PadButton(PadButton, bool),
+    // where a stick is now, already past the deadzone (see input_source.rs)
+    PadAxis(PadAxis, f32),
+    // a character was typed, only sent while the game wants text (see text_field.rs)
+    Char(char),
+    // the mouse aiming setting was switched, it changes what the mouse does so replays need it
+    MouseAim(bool),
+    // the pixel font was switched on or off, menus are as wide as the font draws them so replays need it too
+    BitmapFont(bool),
+}
 
 pub struct Input {
     now_keys: Box<[bool]>,
@@ -9,16 +71,43 @@ pub struct Input {
     prev_mouse: Box<[bool]>,
     now_mouse_pos: MousePos<f64>,
     prev_mouse_pos: MousePos<f64>,
+    // every gamepad at once, indexed by PadButton and PadAxis
+    now_pad: [bool; PadButton::ALL.len()],
+    prev_pad: [bool; PadButton::ALL.len()],
+    now_axes: [f32; PadAxis::ALL.len()],
+    prev_axes: [f32; PadAxis::ALL.len()],
+    // keys that went down since the last frame, in order
+    pressed_keys: Vec<Key>,
+    // characters typed since the last frame
+    typed: String,
+    // text the IME is still putting together, shown but not typed yet
+    preedit: String,
+    // the ship follows the mouse (or a finger) instead of the keys
+    mouse_aim: bool,
+    // the hud is drawn (and measured) with the pixel font instead of the system one
+    bitmap_font: bool,
+    // everything that happened since the last frame
+    events: Vec<InputEvent>,
 }
 impl Default for Input {
     fn default() -> Self {
         Self {
             now_keys: vec![false; 255].into_boxed_slice(),
             prev_keys: vec![false; 255].into_boxed_slice(),
-            now_mouse: vec![false; 16].into_boxed_slice(),
-            prev_mouse: vec![false; 16].into_boxed_slice(),
+            now_mouse: vec![false; MOUSE_BUTTONS].into_boxed_slice(),
+            prev_mouse: vec![false; MOUSE_BUTTONS].into_boxed_slice(),
             now_mouse_pos: MousePos { x: 0.0, y: 0.0 },
             prev_mouse_pos: MousePos { x: 0.0, y: 0.0 },
+            now_pad: [false; PadButton::ALL.len()],
+            prev_pad: [false; PadButton::ALL.len()],
+            now_axes: [0.0; PadAxis::ALL.len()],
+            prev_axes: [0.0; PadAxis::ALL.len()],
+            pressed_keys: vec![],
+            typed: String::new(),
+            preedit: String::new(),
+            mouse_aim: false,
+            bitmap_font: false,
+            events: vec![],
         }
     }
 }
@@ -37,27 +126,51 @@ impl Input {
         !self.now_keys[kc as usize] && self.prev_keys[kc as usize]
     }
     pub fn is_mouse_down(&self, button: MouseButton) -> bool {
-        self.now_mouse[Self::mouse_button_to_usize(button)]
+        Self::mouse_in(&self.now_mouse, button)
+    }
+    // first key that went down this frame, for rebinding controls
+    pub fn first_pressed_key(&self) -> Option<Key> {
+        self.pressed_keys.first().copied()
     }
-    fn mouse_button_to_usize(button: MouseButton) -> usize {
-        match button {
+    // same for mouse buttons
+    pub fn first_pressed_mouse(&self) -> Option<MouseButton> {
+        (0..self.now_mouse.len())
+            .find(|&i| self.now_mouse[i] && !self.prev_mouse[i])
+            .map(|i| match i {
+                0 => MouseButton::Left,
+                1 => MouseButton::Right,
+                2 => MouseButton::Middle,
+                n => MouseButton::Other(n as u16),
+            })
+    }
+    // None for buttons past MOUSE_BUTTONS
+    fn mouse_button_to_usize(button: MouseButton) -> Option<usize> {
+        let i = match button {
             MouseButton::Left => 0,
             MouseButton::Right => 1,
             MouseButton::Middle => 2,
             MouseButton::Other(n) => n as usize,
-        }
+        };
+        (i < MOUSE_BUTTONS).then_some(i)
+    }
+    // whether Input keeps track of this button at all
+    pub fn tracks_mouse_button(button: MouseButton) -> bool {
+        Self::mouse_button_to_usize(button).is_some()
+    }
+    // buttons that aren't kept track of are never down
+    fn mouse_in(buttons: &[bool], mb: MouseButton) -> bool {
+        Self::mouse_button_to_usize(mb).is_some_and(|i| buttons[i])
     }
     pub fn is_mouse_up(&self, mb: MouseButton) -> bool {
-        !self.now_mouse[Self::mouse_button_to_usize(mb)]
+        !Self::mouse_in(&self.now_mouse, mb)
     }
     pub fn is_mouse_pressed(&self, mb: MouseButton) -> bool {
-        self.now_mouse[Self::mouse_button_to_usize(mb)]
-            && !self.prev_mouse[Self::mouse_button_to_usize(mb)]
+        Self::mouse_in(&self.now_mouse, mb) && !Self::mouse_in(&self.prev_mouse, mb)
     }
     pub fn is_mouse_released(&self, mb: MouseButton) -> bool {
-        !self.now_mouse[Self::mouse_button_to_usize(mb)]
-            && self.prev_mouse[Self::mouse_button_to_usize(mb)]
+        !Self::mouse_in(&self.now_mouse, mb) && Self::mouse_in(&self.prev_mouse, mb)
     }
+    // in the game's pixels with 0,0 at the bottom left (main converts it from the window's, see Viewport::to_game)
     pub fn mouse_pos(&self) -> MousePos<f64> {
         self.now_mouse_pos
     }
@@ -67,44 +180,202 @@ impl Input {
             y: self.now_mouse_pos.y - self.prev_mouse_pos.y,
         }
     }
+    pub fn mouse_aim(&self) -> bool {
+        self.mouse_aim
+    }
+    pub fn bitmap_font(&self) -> bool {
+        self.bitmap_font
+    }
     pub fn key_axis(&self, down: Key, up: Key) -> f32 {
         (if self.is_key_down(down) { -1.0 } else { 0.0 })
             + (if self.is_key_down(up) { 1.0 } else { 0.0 })
     }
-    pub fn next_frame(&mut self) {
-        self.prev_keys.copy_from_slice(&self.now_keys);
-        self.prev_mouse.copy_from_slice(&self.now_mouse);
-        self.prev_mouse_pos = self.now_mouse_pos;
+    pub fn is_pad_down(&self, button: PadButton) -> bool {
+        self.now_pad[button as usize]
+    }
+    pub fn is_pad_pressed(&self, button: PadButton) -> bool {
+        self.now_pad[button as usize] && !self.prev_pad[button as usize]
+    }
+    // same as first_pressed_key for gamepad buttons
+    pub fn first_pressed_pad(&self) -> Option<PadButton> {
+        PadButton::ALL.into_iter().find(|&b| self.is_pad_pressed(b))
+    }
+    // where a stick is, -1 to 1
+    pub fn axis(&self, axis: PadAxis) -> f32 {
+        self.now_axes[axis as usize]
+    }
+    pub fn prev_axis(&self, axis: PadAxis) -> f32 {
+        self.prev_axes[axis as usize]
+    }
+    // like key_axis but the stick counts too, whichever is pushed further wins
+    pub fn stick_axis(&self, down: Key, up: Key, axis: PadAxis) -> f32 {
+        let keys = self.key_axis(down, up);
+        let stick = self.axis(axis);
+        if stick.abs() > keys.abs() { stick } else { keys }
+    }
+    // characters typed this frame, in order
+    pub fn typed(&self) -> &str {
+        &self.typed
+    }
+    pub fn preedit(&self) -> &str {
+        &self.preedit
+    }
+    // not an event, it's only ever shown so replays can do without it
+    pub fn set_preedit(&mut self, text: &str) {
+        self.preedit.clear();
+        self.preedit.push_str(text);
+    }
+    // set a key without a winit event, for driving the game headless
+    pub fn set_key_down(&mut self, kc: Key, down: bool) {
+        self.apply(InputEvent::Key(kc, down));
+    }
+    // what happened since the last frame, in order
+    pub fn events(&self) -> &[InputEvent] {
+        &self.events
     }
-    pub fn handle_key_event(&mut self, ke: winit::event::KeyboardInput) {
-        if let winit::event::KeyboardInput {
-            virtual_keycode: Some(keycode),
-            state,
-            ..
-        } = ke
-        {
-            match state {
-                winit::event::ElementState::Pressed => {
-                    self.now_keys[keycode as usize] = true;
+    pub fn apply(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Key(kc, down) => {
+                // key repeat doesn't change anything
+                if self.now_keys[kc as usize] == down {
+                    return;
                 }
-                winit::event::ElementState::Released => {
-                    self.now_keys[keycode as usize] = false;
+                if down {
+                    self.pressed_keys.push(kc);
                 }
+                self.now_keys[kc as usize] = down;
             }
-        }
-    }
-    pub fn handle_mouse_button(&mut self, state: ElementState, button: MouseButton) {
-        let button = Self::mouse_button_to_usize(button);
-        match state {
-            ElementState::Pressed => {
-                self.now_mouse[button] = true;
+            InputEvent::Mouse(button, down) => {
+                // a mouse with more buttons than are kept track of
+                let Some(button) = Self::mouse_button_to_usize(button) else {
+                    return;
+                };
+                if self.now_mouse[button] == down {
+                    return;
+                }
+                self.now_mouse[button] = down;
+            }
+            InputEvent::MouseMove(x, y) => {
+                self.now_mouse_pos = MousePos { x, y };
+                // only where the mouse ended up matters
+                if let Some(InputEvent::MouseMove(..)) = self.events.last() {
+                    self.events.pop();
+                }
+            }
+            InputEvent::PadButton(button, down) => {
+                if self.now_pad[button as usize] == down {
+                    return;
+                }
+                self.now_pad[button as usize] = down;
+            }
+            InputEvent::PadAxis(axis, value) => {
+                if self.now_axes[axis as usize] == value {
+                    return;
+                }
+                self.now_axes[axis as usize] = value;
+                // same as the mouse, sticks send a lot of these
+                if let Some(&InputEvent::PadAxis(last, _)) = self.events.last() {
+                    if last == axis {
+                        self.events.pop();
+                    }
+                }
+            }
+            InputEvent::Char(c) => {
+                // backspace and enter come through as characters too, they're handled as keys
+                if c.is_control() {
+                    return;
+                }
+                self.typed.push(c);
             }
-            ElementState::Released => {
-                self.now_mouse[button] = false;
+            InputEvent::MouseAim(on) => {
+                if self.mouse_aim == on {
+                    return;
+                }
+                self.mouse_aim = on;
+            }
+            InputEvent::BitmapFont(on) => {
+                if self.bitmap_font == on {
+                    return;
+                }
+                self.bitmap_font = on;
             }
         }
+        self.events.push(event);
     }
-    pub fn handle_mouse_move(&mut self, position: MousePos<f64>) {
-        self.now_mouse_pos = position;
+    pub fn next_frame(&mut self) {
+        self.prev_keys.copy_from_slice(&self.now_keys);
+        self.prev_mouse.copy_from_slice(&self.now_mouse);
+        self.prev_mouse_pos = self.now_mouse_pos;
+        self.prev_pad = self.now_pad;
+        self.prev_axes = self.now_axes;
+        self.pressed_keys.clear();
+        self.typed.clear();
+        self.events.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_go_up_and_get_released() {
+        let mut input = Input::default();
+        assert!(input.is_key_up(Key::Space));
+        input.set_key_down(Key::Space, true);
+        assert!(input.is_key_down(Key::Space) && !input.is_key_up(Key::Space));
+        assert_eq!(input.events(), &[InputEvent::Key(Key::Space, true)]);
+        input.next_frame();
+        input.set_key_down(Key::Space, false);
+        assert!(input.is_key_up(Key::Space));
+        assert!(input.is_key_released(Key::Space));
+        // only released the frame it went up
+        input.next_frame();
+        assert!(!input.is_key_released(Key::Space));
+    }
+
+    #[test]
+    fn mouse_buttons_go_up() {
+        let mut input = Input::default();
+        assert!(input.is_mouse_up(MouseButton::Left));
+        input.apply(InputEvent::Mouse(MouseButton::Left, true));
+        assert!(!input.is_mouse_up(MouseButton::Left));
+        input.next_frame();
+        input.apply(InputEvent::Mouse(MouseButton::Left, false));
+        assert!(input.is_mouse_up(MouseButton::Left));
+    }
+
+    #[test]
+    fn mouse_delta_is_how_far_it_moved_since_last_frame() {
+        let mut input = Input::default();
+        input.apply(InputEvent::MouseMove(10.0, 20.0));
+        input.next_frame();
+        input.apply(InputEvent::MouseMove(15.0, 12.0));
+        let delta = input.mouse_delta();
+        assert_eq!((delta.x, delta.y), (5.0, -8.0));
+        input.next_frame();
+        let delta = input.mouse_delta();
+        assert_eq!((delta.x, delta.y), (0.0, 0.0));
+    }
+
+    #[test]
+    fn key_axis_adds_up_both_keys() {
+        let mut input = Input::default();
+        assert_eq!(input.key_axis(Key::Left, Key::Right), 0.0);
+        input.set_key_down(Key::Left, true);
+        assert_eq!(input.key_axis(Key::Left, Key::Right), -1.0);
+        input.set_key_down(Key::Right, true);
+        assert_eq!(input.key_axis(Key::Left, Key::Right), 0.0);
+        input.set_key_down(Key::Left, false);
+        assert_eq!(input.key_axis(Key::Left, Key::Right), 1.0);
+    }
+
+    #[test]
+    fn stick_axis_takes_whichever_is_pushed_further() {
+        let mut input = Input::default();
+        input.apply(InputEvent::PadAxis(PadAxis::LeftStickX, 0.4));
+        assert_eq!(input.stick_axis(Key::Left, Key::Right, PadAxis::LeftStickX), 0.4);
+        input.set_key_down(Key::Left, true);
+        assert_eq!(input.stick_axis(Key::Left, Key::Right, PadAxis::LeftStickX), -1.0);
     }
 }
@@ -0,0 +1,150 @@
+use winit::event::{ElementState, Ime, KeyboardInput, MouseButton, TouchPhase};
+
+use crate::input::{Input, InputEvent, MousePos};
+#[cfg(test)]
+use crate::input::Key;
+
+// Somewhere input comes from: the keyboard and mouse, gamepads, a replay or a script.
+// They all turn what happened into InputEvents, so the game only ever looks at Input
+pub trait InputSource {
+    // called once per update, before it runs, with everything that happened since the last one
+    fn poll(&mut self, input: &mut Input);
+
+    // true once there's nothing left to play (replays and scripts)
+    fn finished(&self) -> bool {
+        false
+    }
+}
+
+// The keyboard and mouse. winit hands these over whenever they happen,
+// they're held here until the next update
+#[derive(Default)]
+pub struct KeyboardMouse {
+    pending: Vec<InputEvent>,
+    // what the IME has so far, see Input::preedit
+    preedit: String,
+    // the finger standing in for the mouse
+    finger: Option<u64>,
+}
+
+impl KeyboardMouse {
+    pub fn handle_key_event(&mut self, ke: KeyboardInput) {
+        if let Some(keycode) = ke.virtual_keycode {
+            self.pending.push(InputEvent::Key(keycode, ke.state == ElementState::Pressed));
+        }
+    }
+
+    pub fn handle_mouse_button(&mut self, state: ElementState, button: MouseButton) {
+        self.pending.push(InputEvent::Mouse(button, state == ElementState::Pressed));
+    }
+
+    pub fn handle_mouse_move(&mut self, position: MousePos<f64>) {
+        self.pending.push(InputEvent::MouseMove(position.x, position.y));
+    }
+
+    // a finger works like the mouse with the left button held down. Only the first finger
+    // on the screen counts until it's lifted
+    pub fn handle_touch(&mut self, id: u64, phase: TouchPhase, position: MousePos<f64>) {
+        if self.finger.is_some_and(|finger| finger != id) {
+            return;
+        }
+        match phase {
+            TouchPhase::Started => {
+                self.finger = Some(id);
+                self.handle_mouse_move(position);
+                self.pending.push(InputEvent::Mouse(MouseButton::Left, true));
+            }
+            TouchPhase::Moved => self.handle_mouse_move(position),
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.finger = None;
+                self.handle_mouse_move(position);
+                self.pending.push(InputEvent::Mouse(MouseButton::Left, false));
+            }
+        }
+    }
+
+    // the settings changed how the mouse is used
+    pub fn set_mouse_aim(&mut self, on: bool) {
+        self.pending.push(InputEvent::MouseAim(on));
+    }
+
+    // the settings switched which font the hud is in
+    pub fn set_bitmap_font(&mut self, on: bool) {
+        self.pending.push(InputEvent::BitmapFont(on));
+    }
+
+    pub fn handle_char(&mut self, c: char) {
+        self.pending.push(InputEvent::Char(c));
+    }
+
+    // typing through an IME (ex. for japanese), finished text comes in as characters
+    pub fn handle_ime(&mut self, ime: Ime) {
+        match ime {
+            Ime::Preedit(text, _) => self.preedit = text,
+            Ime::Commit(text) => {
+                self.preedit.clear();
+                self.pending.extend(text.chars().map(InputEvent::Char));
+            }
+            Ime::Enabled | Ime::Disabled => self.preedit.clear(),
+        }
+    }
+}
+
+impl InputSource for KeyboardMouse {
+    fn poll(&mut self, input: &mut Input) {
+        for event in self.pending.drain(..) {
+            input.apply(event);
+        }
+        input.set_preedit(&self.preedit);
+    }
+}
+
+// A made up device that does what it's told on the updates it's told to,
+// for driving the game from tests without a keyboard (see simulation.rs):
+//     ScriptedInput::default().tap(0, Key::A).hold(10, 40, Key::Right)
+#[cfg(test)]
+#[derive(Default)]
+pub struct ScriptedInput {
+    // update number and what happens before it, kept in order
+    events: Vec<(u64, InputEvent)>,
+    tick: u64,
+    next: usize,
+}
+
+#[cfg(test)]
+impl ScriptedInput {
+    pub fn at(mut self, tick: u64, event: InputEvent) -> Self {
+        // after anything else on the same update so events keep the order they were added in
+        let i = self.events.partition_point(|&(t, _)| t <= tick);
+        self.events.insert(i, (tick, event));
+        self
+    }
+
+    // press a key for one update
+    pub fn tap(self, tick: u64, key: Key) -> Self {
+        self.hold(tick, tick + 1, key)
+    }
+
+    // key is down from one update until another
+    pub fn hold(self, from: u64, to: u64, key: Key) -> Self {
+        self.at(from, InputEvent::Key(key, true)).at(to, InputEvent::Key(key, false))
+    }
+}
+
+#[cfg(test)]
+impl InputSource for ScriptedInput {
+    fn poll(&mut self, input: &mut Input) {
+        while let Some(&(tick, event)) = self.events.get(self.next) {
+            if tick > self.tick {
+                break;
+            }
+            input.apply(event);
+            self.next += 1;
+        }
+        self.tick += 1;
+    }
+
+    fn finished(&self) -> bool {
+        self.next >= self.events.len()
+    }
+}
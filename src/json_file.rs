@@ -0,0 +1,57 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// Something went wrong reading or writing one of the game's .json files
+// (controls, high scores, replays, the sprite atlas and font descriptions)
+#[derive(Debug)]
+pub enum JsonError {
+    Io(PathBuf, std::io::Error),
+    Parse(PathBuf, serde_json::Error),
+}
+
+impl JsonError {
+    pub fn path(&self) -> &Path {
+        match self {
+            JsonError::Io(path, _) | JsonError::Parse(path, _) => path,
+        }
+    }
+
+    // the file just isn't there (yet), ex. nothing has saved controls
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, JsonError::Io(_, e) if e.kind() == std::io::ErrorKind::NotFound)
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonError::Io(path, e) => write!(f, "couldn't read or write {}: {}", path.display(), e),
+            JsonError::Parse(path, e) => write!(f, "{} isn't valid: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, JsonError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| JsonError::Io(path.to_owned(), e))?;
+    serde_json::from_str(&text).map_err(|e| JsonError::Parse(path.to_owned(), e))
+}
+
+// indented, for files people might open and edit
+pub fn save<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<(), JsonError> {
+    let path = path.as_ref();
+    let text = serde_json::to_string_pretty(value).map_err(|e| JsonError::Parse(path.to_owned(), e))?;
+    std::fs::write(path, text).map_err(|e| JsonError::Io(path.to_owned(), e))
+}
+
+// all on one line, for big files nobody reads (replays)
+pub fn save_compact<T: Serialize>(path: impl AsRef<Path>, value: &T) -> Result<(), JsonError> {
+    let path = path.as_ref();
+    let text = serde_json::to_string(value).map_err(|e| JsonError::Parse(path.to_owned(), e))?;
+    std::fs::write(path, text).map_err(|e| JsonError::Io(path.to_owned(), e))
+}
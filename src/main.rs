@@ -1,46 +1,139 @@
 use std::borrow::Cow;
+use std::path::{Path, PathBuf};
 
 mod input;
 mod game_state;
-use rand::Rng;
-use bytemuck::{Pod, Zeroable};
+mod sprite;
+mod scene;
+mod title;
+mod block_game;
+mod space_game;
+mod simulation;
+mod clock;
+mod camera;
+mod raster;
+mod atlas;
+mod sprite_batch;
+mod particles;
+mod tween;
+mod animation;
+mod settings;
+mod hud;
+mod text_layer;
+mod bitmap_font;
+mod post;
+mod hot_reload;
+mod assets;
+mod missing_assets;
+mod actions;
+mod controls_menu;
+mod replay;
+mod input_source;
+mod gamepad;
+mod text_field;
+mod high_scores;
+mod menu;
+mod json_file;
 use winit::{
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::Window,
 };
-use glyphon::{
-    Attrs, Buffer, Color, Family, FontSystem, Metrics, Resolution, Shaping, SwashCache, TextArea,
-    TextAtlas, TextBounds, TextRenderer,
-};
+use glyphon::{FontSystem, Resolution, SwashCache, TextAtlas, TextRenderer};
 use wgpu::{
     CompositeAlphaMode, MultisampleState, 
 };
-use game_state::GameState;
+use simulation::Simulation;
+use clock::FixedTimestep;
+use camera::Viewport;
+use settings::Settings;
+use atlas::SpriteAtlas;
+use sprite_batch::SpriteBatch;
+use text_layer::TextLayer;
+use post::PostProcess;
+use hot_reload::Watcher;
+use assets::Assets;
+use missing_assets::MissingAssetsScene;
+use title::TitleScene;
+use actions::{ActionMap, ActionsError};
+use high_scores::HighScores;
+use replay::{Replay, ReplayPlayer};
+use input_source::{InputSource, KeyboardMouse};
+use gamepad::Gamepads;
+use hud::{Anchor, MeasureText, TextBlock};
+use input::MousePos;
 
 pub const WINDOW_WIDTH: f32 = 1024.0;
 pub const WINDOW_HEIGHT: f32 = 768.0;
 pub const SPRITE_SIZE: f32 = 64.0;
 
+// what hot reloading (--dev) watches, relative to the asset root
+const SHADER_PATH: &str = "src/shader.wgsl";
+const CONTENT_PATH: &str = "content";
+
 
 
 
 // In WGPU, we define an async function whose operation can be suspended and resumed.
 // This is because on web, we can't take over the main event loop and must leave it to
 // the browser.  On desktop, we'll just be running this function to completion.
-async fn run(event_loop: EventLoop<()>, window: Window) {
-    // state of game at any time
-    let mut gs = game_state::init_game_state();
-
-
-    // sprite struct
-    #[repr(C)]
-    #[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
-    #[derive(Debug)]
-    struct GPUSprite {
-        to_region: [f32;4],
-        from_region: [f32;4],
+async fn run(event_loop: EventLoop<()>, window: Window, options: Options) {
+    // --replay plays a recorded run back, it brings its own seed, tick rate and controls
+    let replay = options.replay.as_ref().map(|path| Replay::load(path).unwrap_or_else(|e| panic!("{}", e)));
+    // the game updates at a fixed rate (60 per second unless TICK_RATE is set),
+    // separate from how often frames get drawn
+    let mut timestep = match &replay {
+        Some(replay) => FixedTimestep::new(replay.tick_rate),
+        None => FixedTimestep::from_env(),
+    };
+    // everything random in the game comes from this, pass --seed to get the same run again
+    let seed = replay.as_ref().map(|replay| replay.seed).or(options.seed).unwrap_or_else(rand::random);
+    log::info!("seed: {}", seed);
+    // every file the game loads goes through here, anything missing ends up in assets.errors()
+    let mut assets = Assets::new(assets::find_root());
+    // names for the parts of the sprite sheet, kept in a .json file next to the PNG
+    let sprite_atlas = assets.atlas(assets::SPRITE_ATLAS);
+    let sprite_image = sprite_atlas.as_ref().map(|atlas| atlas.image_path.clone());
+    // pixel font for the hud, used instead of the system font when settings.bitmap_font is on
+    let mut bitmap_font = assets.font(assets::FONT);
+    // system fonts for the hud otherwise, glyphon shapes text with these
+    let mut font_system = FontSystem::new();
+    // state of game at any time, along with its sprites
+    let mut sim = Simulation::new(timestep.dt(), sprite_atlas.unwrap_or_else(SpriteAtlas::empty), seed, &mut font_system);
+    // which keys do what. there's no file until the controls menu changes something
+    let controls_path = assets.path(assets::CONTROLS);
+    sim.actions = match ActionMap::load(&controls_path) {
+        Ok(actions) => actions,
+        Err(ActionsError::File(e)) if e.is_not_found() => ActionMap::default(),
+        Err(e) => {
+            log::error!("{}, using the default controls", e);
+            ActionMap::default()
+        }
+    };
+    let high_scores_path = assets.path(assets::HIGH_SCORES);
+    sim.high_scores = match HighScores::load(&high_scores_path) {
+        Ok(high_scores) => high_scores,
+        Err(e) if e.is_not_found() => HighScores::default(),
+        Err(e) => {
+            log::error!("{}, starting with no high scores", e);
+            HighScores::default()
+        }
+    };
+    if let Some(replay) = &replay {
+        sim.actions = replay.actions.clone();
+        sim.high_scores = replay.high_scores.clone();
     }
+    let mut saved_actions = sim.actions.clone();
+    let mut saved_high_scores = sim.high_scores.clone();
+    // --record saves the run when the window is closed
+    let mut recording = options.record.as_ref().map(|_| Replay::new(seed, timestep.tick_rate(), sim.actions.clone(), sim.high_scores.clone()));
+    // while this is playing the keyboard, mouse and gamepads are ignored
+    let mut player = replay.map(ReplayPlayer::new);
+    let mut keyboard = KeyboardMouse::default();
+    let mut gamepads = Gamepads::new();
+    // whether the IME is turned on, it is while the game wants typed text
+    let mut ime_allowed = false;
+
 
     #[repr(C)]
     #[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
@@ -49,23 +142,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
         y: f32,
     }
 
-    // camera struct
-    #[repr(C)]
-    #[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
-    struct GPUCamera {
-        screen_pos: [f32;2],
-        screen_size: [f32;2]
-    }
-
-    // camera stuff
-    let camera = GPUCamera {
-        screen_pos: [0.0, 0.0],
-        // Consider using config.width and config.height instead,
-        // it's up to you whether you want the window size to change what's visible in the game
-        // or scale it up and down
-        //              x       y
-        screen_size: [WINDOW_WIDTH, WINDOW_HEIGHT],
-    };
+    // camera stuff - lives in the simulation since the game moves it (the block game scrolls up the tower).
+    // always the same amount of the game is visible, the Viewport scales it to fit the window
 
     // VECTOR OF POS OF OUR SPRITES
     // MATH CORDS = 0,0 == BOTTOM LEFT
@@ -80,27 +158,8 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
     //     });
     //     i += 1;
     // }
-    let mut rng = rand::thread_rng();
-    // number of max dropped per row * 12 is the maximum number of sprites needed for the game.
-    // MAKE BUFFER BIGGER FOR SPACE GAME
-    let mut sprites:Vec<_> = (0..gs.drop_sprite_blocks*36).map(|_| GPUSprite{
-        to_region: 
-            [WINDOW_WIDTH/2.0,
-            WINDOW_HEIGHT,
-            0.0, // generate width and height to be 0 so that you can adjust later, but are now invisible
-            0.0], 
-        from_region:[
-            0.25, // + rng.gen_range(0..2) as f32*0.25, // each row needs to be the same color, so all random doesn't do anything
-            0.0, // + rng.gen_range(0..10) as f32*0.1,
-            0.25,
-            0.1],
-    }).collect();
     
     
-    use std::path::Path;
-    let img = image::open(Path::new("content/block-sprites.png")).expect("Should be a valid image at path content/block-sprites.png'");
-    let img = img.to_rgba8();
-    let size = window.inner_size();
     let instance = wgpu::Instance::default();
     let surface = unsafe { instance.create_surface(&window) }.unwrap();
     let adapter = instance
@@ -137,118 +196,60 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
 
     let buffer_camera = device.create_buffer(&wgpu::BufferDescriptor{
         label: None,
-        size: bytemuck::bytes_of(&camera).len() as u64,
+        size: bytemuck::bytes_of(&sim.camera).len() as u64,
         usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         mapped_at_creation: false
     });
-    let buffer_sprite = device.create_buffer(&wgpu::BufferDescriptor{
+    // bitmap font text is laid out in screen space, so it gets a camera that never moves
+    let buffer_hud_camera = device.create_buffer(&wgpu::BufferDescriptor{
         label: None,
-        size: (bytemuck::cast_slice::<_,u8>(&sprites).len()) as u64,
-        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        size: bytemuck::bytes_of(&sim.camera).len() as u64,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         mapped_at_creation: false
     });
-    // have sprites be a fixed length, track how far you have gone into sprites,
-    // when disappeared, make width and height = 0
-
-
-
-        let (img_w, img_h) = img.dimensions();
-        // How big is the texture in GPU memory?
-        let size = wgpu::Extent3d {
-            width: img_w,
-            height: img_h,
-            depth_or_array_layers: 1,
-        };
-        // Let's make a texture now
-        let texture = device.create_texture(
-            // Parameters for the texture
-            &wgpu::TextureDescriptor {
-                // An optional label
-                label: Some("47 image"),
-                // Its dimensions. This line is equivalent to size:size
-                size,
-                // Number of mipmapping levels (to show different pictures at different distances)
-                mip_level_count: 1,
-                // Number of samples per pixel in the texture. It'll be one for our whole class.
-                sample_count: 1,
-                // Is it a 1D, 2D, or 3D texture?
-                dimension: wgpu::TextureDimension::D2,
-                // 8 bits per component, four components per pixel, unsigned, normalized in 0..255, SRGB
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                // This texture will be bound for shaders and have stuff copied to it
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                // What formats are allowed as views on this texture besides the native format
-                view_formats: &[],
-            }
-        );
-        // Now that we have a texture, we need to copy its data to the GPU:
-        queue.write_texture(
-            // A description of where to write the image data.
-            // We'll use this helper to say "the whole texture"
-            texture.as_image_copy(),
-            // The image data to write
-            &img,
-            // What portion of the image data to copy from the CPU
-            wgpu::ImageDataLayout {
-                // Where in img do the bytes to copy start?
-                offset: 0,
-                // How many bytes in each row of the image?
-                bytes_per_row: Some(4*img_w),
-                // We could pass None here and it would be alright,
-                // since we're only uploading one image
-                rows_per_image: Some(img_h),
-            },
-            // What portion of the texture we're writing into
-            size
-        );
+    queue.write_buffer(&buffer_hud_camera, 0, bytemuck::bytes_of(&camera::GPUCamera::default()));
+
+
 
         // ADD DATA INTO THE BUFFERS!!!!
-        queue.write_buffer(&buffer_camera, 0, bytemuck::bytes_of(&camera));
-        queue.write_buffer(&buffer_sprite, 0, bytemuck::cast_slice(&sprites));
-        //queue.write_buffer(&buffer_sprite, 0, bytemuck::cast_slice(&sprites));
+        queue.write_buffer(&buffer_camera, 0, bytemuck::bytes_of(&sim.camera));
 
 
     // The swapchain is how we obtain images from the surface we're drawing onto.
     // This is so we can draw onto one image while a different one is being presentedto the user on-screen.
     let swapchain_capabilities = surface.get_capabilities(&adapter);
     let swapchain_format = swapchain_capabilities.formats[0];
+    // inner_size is in physical pixels already
+    let window_size = window.inner_size();
     let mut config = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
         format: swapchain_format,
-        width: size.width,
-        height: size.height,
+        width: window_size.width,
+        height: window_size.height,
         present_mode: wgpu::PresentMode::Fifo,
         alpha_mode: CompositeAlphaMode::Opaque,
         view_formats: vec![],
     };
     surface.configure(&device, &config);
+    // everything is drawn into here first, then through the effects turned on in settings onto the window
+    let mut post = PostProcess::new(&device, swapchain_format, config.width, config.height);
 
 
     // Set up text renderer
-    let mut font_system = FontSystem::new();
     let mut cache = SwashCache::new();
     let mut atlas = TextAtlas::new(&device, &queue, swapchain_format);
     let mut text_renderer =
         TextRenderer::new(&mut atlas, &device, MultisampleState::default(), None);
-    let mut buffer = Buffer::new(&mut font_system, Metrics::new(30.0, 42.0));
+    // every piece of text the screens put in their hud, shaped and placed
+    let mut text_layer = TextLayer::new();
 
 
-    let physical_width = (size.width as f64 * window.scale_factor()) as f32;
-    let physical_height = (size.height as f64 * window.scale_factor()) as f32;
+    // where in the window the game gets drawn, the camera always sees WINDOW_WIDTH x WINDOW_HEIGHT
+    // and this decides how that is fit into however big the window really is
+    let mut settings = Settings::default();
+    let mut viewport = Viewport::new(settings.scale_policy, config.width, config.height);
 
 
-    buffer.set_size(&mut font_system, WINDOW_WIDTH, WINDOW_HEIGHT);
-    buffer.set_text(&mut font_system, "Block Games!!!\nPress 1 for Falling Blocks\nPress 2 for Space Blocks", Attrs::new().family(Family::Serif), Shaping::Advanced);
-    buffer.shape_until_scroll(&mut font_system);
-
-    // Load the shaders from disk.  Remember, shader programs are things we compile for
-    // our GPU so that it can compute vertices and colorize fragments.
-    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: None,
-        // Cow is a "copy on write" wrapper that abstracts over owned or borrowed memory.
-        // Here we just need to use it since wgpu wants "some text" to compile a shader from.
-        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("shader.wgsl"))),
-    });
 
 
 
@@ -257,9 +258,26 @@ async fn run(event_loop: EventLoop<()>, window: Window) {
     // uses helper function to load image
     // let tex_47 = load_texture("content/king.png", Some("king image"), &device, &queue)
     // .expect("Couldn't load sprite img");
-    let (tex_sprite, mut img_bkgd) = load_texture("content/block-sprites.png", Some("sprite image"), &device, &queue).expect("Couldn't load sprite img");
-    let view_sprite = tex_sprite.create_view(&wgpu::TextureViewDescriptor::default());
+    let tex_sprite = match &sprite_image {
+        Some(path) => assets.texture(&device, &queue, path),
+        None => assets.placeholder(&device, &queue),
+    };
     let sampler_sprite = device.create_sampler(&wgpu::SamplerDescriptor::default());
+    let tex_font = match &bitmap_font {
+        Some(font) => assets.texture(&device, &queue, &font.image_path),
+        None => assets.placeholder(&device, &queue),
+    };
+    // can't play without the files, say which ones instead of crashing
+    let mut missing_assets = !assets.errors().is_empty();
+    if missing_assets {
+        for e in assets.errors() {
+            log::error!("{}", e);
+        }
+        sim.go_to(Box::new(MissingAssetsScene {
+            errors: assets.errors().iter().map(|e| e.to_string()).collect(),
+            root: assets.root.display().to_string(),
+        }), &mut font_system);
+    }
 
 
 
@@ -342,37 +360,15 @@ let sprite_bind_group_layout =
         ],
     });
 
-    // BIND GROUP!!
-    let sprite_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: None,
-        layout: &sprite_bind_group_layout,
-        entries: &[
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: buffer_camera.as_entire_binding()
-            },
-            wgpu::BindGroupEntry {
-                binding: 1,
-                resource: buffer_sprite.as_entire_binding()
-            }
-        ],
-    });
+    // BIND GROUP!! the sprite batch owns the sprite buffer and grows it when the game needs more sprites
+    let mut sprite_batch = SpriteBatch::new(&device, &sprite_bind_group_layout, &buffer_camera, sim.sprites.len());
+    // and a second one for bitmap font glyphs
+    let mut text_batch = SpriteBatch::new(&device, &sprite_bind_group_layout, &buffer_hud_camera, 256);
 
-    let texture_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: None,
-        layout: &texture_bind_group_layout,
-        entries: &[
-            // One for the texture, one for the sampler
-            wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::TextureView(&view_sprite),
-            },
-            wgpu::BindGroupEntry {
-                binding: 1,
-                resource: wgpu::BindingResource::Sampler(&sampler_sprite),
-            },
-        ],
-    });
+    // textures can be swapped out when hot reloading, so these get rebuilt then
+    let mut texture_bind_group = create_texture_bind_group(&device, &texture_bind_group_layout, assets.get(tex_sprite), &sampler_sprite);
+    // same sampler, the font's own texture
+    let mut font_bind_group = create_texture_bind_group(&device, &texture_bind_group_layout, assets.get(tex_font), &sampler_sprite);
 
 
 
@@ -387,46 +383,17 @@ let sprite_bind_group_layout =
 
     // Our specific "function" is going to be a draw call using our shaders. That's what we
     // set up here, calling the result a render pipeline.  I
-    let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-        label: None,
-        layout: Some(&pipeline_layout),
-        vertex: wgpu::VertexState {
-            module: &shader,
-            entry_point: "vs_main",
-            buffers: &[],
-        },
-        fragment: Some(wgpu::FragmentState {
-            module: &shader,
-            entry_point: "fs_main",
-            targets: &[Some(swapchain_format.into())],
-        }),
-        primitive: wgpu::PrimitiveState::default(),
-        depth_stencil: None,
-        multisample: wgpu::MultisampleState::default(),
-        multiview: None,
-    });
+    let mut render_pipeline = create_sprite_pipeline(&device, &pipeline_layout, include_str!("shader.wgsl"), swapchain_format);
+
+    // with --dev, changes to the shader or anything in content/ show up without restarting.
+    // whatever went wrong with the last reload is shown on screen instead of crashing
+    let mut watcher = options.dev.then(|| Watcher::new(&[&assets.path(SHADER_PATH), &assets.path(CONTENT_PATH)]));
+    let mut shader_error: Option<String> = None;
+    let mut content_error: Option<String> = None;
     // Definitions to control  input
     // Create a new instance of the input mod to use for the event loop
     let mut input = input::Input::default();
 
-    // IMITATE GAME MODE SETTING IMPLEMENTATION FROM MAIN SCREEN
-    // 1: EASY (start with 5, speed is 4)
-    // 2: INTERMEDIATE (start with 4, speed is 6)
-    // 3: HARD (start with 3, speed is 10)
-    let mut game_mode: u8 = 1;
-
-
-    if game_mode == 1 {
-        gs.drop_sprite_blocks = 5;
-        gs.speed = 4;
-    }else if game_mode == 2{
-        gs.drop_sprite_blocks = 4;
-        gs.speed = 6;
-    }else{ // game_mode == 3
-        gs.drop_sprite_blocks = 3;
-        gs.speed = 10;
-    }
-
     // renders everything in the window every frame --> if we update sprite pos here, they will update
     event_loop.run(move |event, _, control_flow| {
         
@@ -447,19 +414,77 @@ let sprite_bind_group_layout =
             event: WindowEvent::KeyboardInput { input: key_ev, .. },
             ..
         } => {
-            input.handle_key_event(key_ev);
+            // F1 switches how the game is scaled to the window
+            if pressed_once(&key_ev, &input, VirtualKeyCode::F1) {
+                settings.scale_policy = settings.scale_policy.next();
+                viewport = Viewport::new(settings.scale_policy, config.width, config.height);
+                log::info!("scale policy: {:?}", settings.scale_policy);
+            }
+            // F2 switches between the system font and the pixel font. Menus are clicked where their
+            // text is drawn, so like F7 it goes through input and a replay keeps the one it recorded
+            if pressed_once(&key_ev, &input, VirtualKeyCode::F2) && player.is_none() {
+                settings.bitmap_font = !settings.bitmap_font;
+                keyboard.set_bitmap_font(settings.bitmap_font);
+            }
+            // F3 to F6 turn the full-screen effects on and off
+            if pressed_once(&key_ev, &input, VirtualKeyCode::F3) {
+                settings.crt_scanlines = !settings.crt_scanlines;
+            }
+            if pressed_once(&key_ev, &input, VirtualKeyCode::F4) {
+                settings.vignette = !settings.vignette;
+            }
+            if pressed_once(&key_ev, &input, VirtualKeyCode::F5) {
+                settings.bloom = !settings.bloom;
+            }
+            if pressed_once(&key_ev, &input, VirtualKeyCode::F6) {
+                settings.flash = !settings.flash;
+            }
+            // F7 makes the ship follow the mouse instead of the arrow keys. It changes how the game
+            // plays so it goes through input like a key would, and a replay keeps the one it recorded
+            if pressed_once(&key_ev, &input, VirtualKeyCode::F7) && player.is_none() {
+                settings.mouse_aim = !settings.mouse_aim;
+                keyboard.set_mouse_aim(settings.mouse_aim);
+                log::info!("mouse aim: {}", settings.mouse_aim);
+            }
+            if player.is_none() {
+                keyboard.handle_key_event(key_ev);
+            }
         },
+        // the mouse is ignored while a replay plays
         Event::WindowEvent {
             event: WindowEvent::MouseInput { state, button, .. },
             ..
-        } => {
-            input.handle_mouse_button(state, button);
+        } if player.is_none() => {
+            keyboard.handle_mouse_button(state, button);
         },
         Event::WindowEvent {
             event: WindowEvent::CursorMoved { position, .. },
             ..
-        } => {
-            input.handle_mouse_move(position);
+        } if player.is_none() => {
+            // the game only ever sees the mouse in its own pixels, so it works at any window size
+            let [x, y] = viewport.to_game(position.x as f32, position.y as f32);
+            keyboard.handle_mouse_move(MousePos { x: x as f64, y: y as f64 });
+        },
+        // touch moves the ship and fires like the mouse does
+        Event::WindowEvent {
+            event: WindowEvent::Touch(touch),
+            ..
+        } if player.is_none() => {
+            let [x, y] = viewport.to_game(touch.location.x as f32, touch.location.y as f32);
+            keyboard.handle_touch(touch.id, touch.phase, MousePos { x: x as f64, y: y as f64 });
+        },
+        // typed characters only matter while something on screen is being typed into
+        Event::WindowEvent {
+            event: WindowEvent::ReceivedCharacter(c),
+            ..
+        } if player.is_none() && sim.wants_text() => {
+            keyboard.handle_char(c);
+        },
+        Event::WindowEvent {
+            event: WindowEvent::Ime(ime),
+            ..
+        } if player.is_none() => {
+            keyboard.handle_ime(ime);
         },
 
         Event::WindowEvent {
@@ -470,807 +495,144 @@ let sprite_bind_group_layout =
             config.width = size.width;
             config.height = size.height;
             surface.configure(&device, &config);
+            post.resize(&device, config.width, config.height);
+            viewport = Viewport::new(settings.scale_policy, config.width, config.height);
             // On macos the window needs to be redrawn manually after resizing
             window.request_redraw();
         },
 
 
         Event::MainEventsCleared => {
-            // Reset to title screen when esc is pressed anywhere
-            if input.is_key_down(winit::event::VirtualKeyCode::Escape){
-                gs.screen = 0;
-                sprites = (0..gs.drop_sprite_blocks*12).map(|_| GPUSprite{
-                    to_region: 
-                        [WINDOW_WIDTH,
-                        WINDOW_HEIGHT,
-                        0.0, // generate width and height to be 0 so that you can adjust later, but are now invisible
-                        0.0], 
-                    from_region:[
-                        0.25, 
-                        0.0, 
-                        0.25,
-                        0.1],
-                }).collect();
+            // all the gameplay happens in here, run as many fixed updates as the time since
+            // the last frame calls for. Input is advanced per update so key presses aren't lost or doubled
+            for _ in 0..timestep.advance() {
+                // a replay stands in for the keyboard, mouse and gamepads until it runs out
+                match &mut player {
+                    Some(replay) => {
+                        replay.poll(&mut input);
+                        gamepads.discard();
+                    }
+                    None => {
+                        keyboard.poll(&mut input);
+                        gamepads.poll(&mut input);
+                    }
+                }
+                if player.as_ref().is_some_and(|replay| replay.finished()) {
+                    log::info!("replay finished");
+                    player = None;
+                }
+                if let Some(recording) = &mut recording {
+                    recording.record(&input);
+                }
+                sim.step(&input, hud_font(&mut bitmap_font, &mut font_system, input.bitmap_font()));
+                input.next_frame();
             }
-            // Check for screen number
-            // Screen number: 0 = Title, 1 = Block Game, 2 = Block Setup, 3 = Black GO, 4 = Space Game, 5 = Space Setup, 6 = Space GO
-            // TITLE SCREEN
-            if gs.screen == 0 {
-
-                buffer.set_text(&mut font_system, "Block Games!!!\nPress a for Falling Blocks\nPress b for Space Blocks", Attrs::new().family(Family::Serif), Shaping::Advanced);
-                if input.is_key_down(winit::event::VirtualKeyCode::A){
-                    gs.screen = 2;
-
-
-                }else if input.is_key_down(winit::event::VirtualKeyCode::B){
-
-                    gs = game_state::init_game_state();
-                    gs.screen = 5;
-                    gs.start = true;
-                    //current x,y of ship
-                    let bullet_speed: f32 = 5.0;
-                    let cur_x: f32 = sprites[1].to_region[0];
-                    let mut cur_y: f32 = sprites[1].to_region[1];
-
-
-                    
-
-                    
-                    }else{
-
-                    // FIRST SCREEN - TITLE SCREEN
-                    // sprites[0].to_region = [
-                    //     0.0, 
-                    //     WINDOW_HEIGHT/2.0, 
-                    //     WINDOW_HEIGHT/2.0, 
-                    //     WINDOW_HEIGHT/2.0];
-                    // sprites[0].from_region = [
-                    //     0.25, 
-                    //     0.1,
-                    //     0.25,
-                    //     0.1];
+            // keep controls.json up to date with the controls menu
+            if sim.actions != saved_actions {
+                if let Err(e) = sim.actions.save(&controls_path) {
+                    log::error!("{}", e);
                 }
-
-
-            } else if gs.screen == 5 {
-                        let sprites_num = sprites.len();
-                        ////println!("Number of elements in the Vec: {}", sprites_num);
-                        // space game
-                        let text_1 = "Target practice! Hit the target for points! \nYour score: ";
-                        let text = text_1.to_owned() + &gs.score.to_string();
-                        buffer.set_text(&mut font_system, &text, Attrs::new().family(Family::Serif), Shaping::Advanced);
-
-                        gs.screen = 5;
-                        let mut hits: f32 = 0.0;
-
-
-                        if gs.start{
-                        // target sprite
-                        let mut hit:bool = false;
-                        let mut hit2:bool = false;
-                        let mut hit3:bool = false;
-
-                        sprites[0].to_region = [
-                            gs.target_x, 
-                            gs.target_y, 
-                            SPRITE_SIZE, 
-                            SPRITE_SIZE];
-                        sprites[0].from_region = [
-                            0.75, 
-                            0.0,
-                            0.25,
-                            0.1];
-                            let mut moven = gs.speed as f32;
-                            if gs.direction == true{
-                                moven = gs.speed as f32 * (-1.0);
-                            }
-
-                                if sprites[0].to_region[1] == WINDOW_HEIGHT - SPRITE_SIZE{
-                                 gs.target_x = sprites[0].to_region[0];
-                                    if gs.target_x >= 960.0 - moven{
-                                        gs.direction = true;
-                                    }else if gs.target_x < 0.0 + moven{
-                                        gs.direction = false;
-                                    }
-                                    gs.target_x = gs.target_x + moven;
-                                    sprites[0].to_region = [gs.target_x, WINDOW_HEIGHT - SPRITE_SIZE, SPRITE_SIZE, SPRITE_SIZE];
-
-                            }     
-                                    
-                        // ship sprite VVV
-                        sprites[1].to_region = [
-                            gs.cur_x, 
-                            gs.cur_y, 
-                            SPRITE_SIZE, 
-                            SPRITE_SIZE];
-                        sprites[1].from_region = [
-                            0.75, 
-                            0.9,
-                            0.25,
-                            0.1];
-
-                        // Bullet Sprites - initially invisible
-
-                        // bullet 1
-                        sprites[2].to_region = [
-                            sprites[1].to_region[0], 
-                            sprites[1].to_region[1], 
-                            // initially invisible
-                            0.0, 
-                            0.0];
-                        sprites[2].from_region = [
-                            0.5, 
-                            0.9,
-                            0.25,
-                            0.1];
-
-                        // bullet 2
-                        sprites[3].to_region = [
-                            sprites[1].to_region[0], 
-                            sprites[1].to_region[1], 
-                            // initially invisible
-                            0.0, 
-                            0.0];
-                        sprites[3].from_region = [
-                            0.5, 
-                            0.9,
-                            0.25,
-                            0.1];
-
-                        // bullet 2
-                        sprites[4].to_region = [
-                            sprites[1].to_region[0], 
-                            sprites[1].to_region[1], 
-                            // initially invisible
-                            0.0, 
-                            0.0];
-                        sprites[4].from_region = [
-                            0.5, 
-                            0.9,
-                            0.25,
-                            0.1];
-
-                        //let mut bullet_sprites: Vec<GPUSprite> = vec![];
-
-                        // checks left and right movement
-                        if input.is_key_down(winit::event::VirtualKeyCode::Left){
-                            ////println!("Left");
-                            gs.cur_x -= 6.0;
-                            sprites[1].to_region = [gs.cur_x, 0.0, SPRITE_SIZE, SPRITE_SIZE];
-                            ////println!("{}", gs.cur_x);
-
-                            if input.is_key_down(winit::event::VirtualKeyCode::Space){
-                                ////println!("Space");
-                                gs.bullet_count +=1;
-      
-                                // //new 
-                                // let mut new_sprite = GPUSprite { to_region:
-                                //     [gs.cur_x, 
-                                //     gs.cur_y, 
-                                //     // initially invisible
-                                //     SPRITE_SIZE, 
-                                //     SPRITE_SIZE],
-                                // from_region: [
-                                //     0.5, 
-                                //     0.9,
-                                //     0.25,
-                                //     0.1]
-                                // };
-                                // let mut splice_num = gs.bullet_count + 3;
-                                // sprites[splice_num] = new_sprite;
-                                // //println!("{:#?}", new_sprite);
-                                // gs.bullet_moving = true;
-    
-    
-    
-                                // // working VVVV
-                                //println!("{}", gs.bullet_count.to_string());
-      
-                                // check if 0 bullets on screen
-                                //println!("BRUHH");
-                                gs.bullet_moving = true;
-                                gs.bullet_x = gs.cur_x;
-                                gs.bullet_y = gs.cur_y;
-                                sprites[2].to_region = [gs.bullet_x, gs.bullet_y, SPRITE_SIZE/4.0, SPRITE_SIZE/4.0];
-
-
-                                if gs.bullet_count == 2{
-                                    gs.bullet2_x = gs.cur_x;
-                                    gs.bullet2_y = gs.cur_y;
-                                    sprites[3].to_region = [gs.bullet2_x, gs.bullet2_y, SPRITE_SIZE/4.0, SPRITE_SIZE/4.0];
-                                }  
-
-                                if gs.bullet_count == 3{
-                                    gs.bullet3_x = gs.cur_x;
-                                    gs.bullet3_y = gs.cur_y;
-                                    sprites[4].to_region = [gs.bullet3_x, gs.bullet3_y, SPRITE_SIZE/4.0, SPRITE_SIZE/4.0];
-                                }             
-
-
-                                }
-
-                            // allows left and right movement simultaneously
-                            // if input.is_key_down(winit::event::VirtualKeyCode::Space){
-                            //     gs.bullet_moving = true;
-                            //     gs.bullet_x = gs.cur_x;
-                            //     gs.bullet_y = gs.cur_y;
-                            //     sprites[2].to_region = [gs.bullet_x, gs.bullet_y, SPRITE_SIZE, SPRITE_SIZE];
-                            // }
-                        }
-
-                        else if input.is_key_down(winit::event::VirtualKeyCode::Right){
-                            //println!("Right");
-                            gs.cur_x += 6.0;
-                            sprites[1].to_region = [gs.cur_x, 0.0, SPRITE_SIZE, SPRITE_SIZE];
-                            //println!("{}", gs.cur_x);
-
-                            if input.is_key_down(winit::event::VirtualKeyCode::Space){
-                                //println!("Space");
-                                gs.bullet_count +=1;
-      
-                                // //new 
-                                // let mut new_sprite = GPUSprite { to_region:
-                                //     [gs.cur_x, 
-                                //     gs.cur_y, 
-                                //     // initially invisible
-                                //     SPRITE_SIZE, 
-                                //     SPRITE_SIZE],
-                                // from_region: [
-                                //     0.5, 
-                                //     0.9,
-                                //     0.25,
-                                //     0.1]
-                                // };
-
-                                // let mut splice_num = gs.bullet_count + 3;
-                                // gs.bullet_index = splice_num;
-                                // sprites[splice_num] = new_sprite;
-                                // //println!("{:#?}", new_sprite);
-                                // gs.bullet_moving = true;
-    
-    
-    
-                                // // working VVVV
-                                //println!("{}", gs.bullet_count.to_string());
-      
-                                // check if 0 bullets on screen
-                                //println!("BRUHH");
-                                gs.bullet_moving = true;
-                                gs.bullet_x = gs.cur_x;
-                                gs.bullet_y = gs.cur_y;
-                                sprites[2].to_region = [gs.bullet_x, gs.bullet_y, SPRITE_SIZE, SPRITE_SIZE];
-                                // increment bullet counter
-                                gs.bullet_count+=1;
-
-
-                                if gs.bullet_count == 2{
-                                    gs.bullet2_x = gs.cur_x;
-                                    gs.bullet2_y = gs.cur_y;
-                                    sprites[3].to_region = [gs.bullet2_x, gs.bullet2_y, SPRITE_SIZE, SPRITE_SIZE];
-                                }  
-
-                                if gs.bullet_count == 3{
-                                    gs.bullet3_x = gs.cur_x;
-                                    gs.bullet3_y = gs.cur_y;
-                                    sprites[4].to_region = [gs.bullet3_x, gs.bullet3_y, SPRITE_SIZE, SPRITE_SIZE];
-                                }             
-
-                                
-                                }
- 
-                        } else if input.is_key_down(winit::event::VirtualKeyCode::Space){
-                                //println!("Space");
-                                gs.bullet_count +=1;
-      
-                                // //new 
-                                // let mut new_sprite = GPUSprite { to_region:
-                                //     [gs.cur_x, 
-                                //     gs.cur_y, 
-                                //     // initially invisible
-                                //     SPRITE_SIZE, 
-                                //     SPRITE_SIZE],
-                                // from_region: [
-                                //     0.5, 
-                                //     0.9,
-                                //     0.25,
-                                //     0.1]
-                                // };
-
-                                // let mut splice_num = gs.bullet_count + 3;
-                                // gs.bullet_index = splice_num;
-                                // sprites[splice_num] = new_sprite;
-                                // //println!("{:#?}", new_sprite);
-                                // gs.bullet_moving = true;
-    
-    
-    
-                                // // working VVVV
-                                //println!("{}", gs.bullet_count.to_string());
-      
-                                // check if 0 bullets on screen
-                                //println!("BRUHH");
-                                gs.bullet_moving = true;
-                                gs.bullet_x = gs.cur_x;
-                                gs.bullet_y = gs.cur_y;
-                                sprites[2].to_region = [gs.bullet_x, gs.bullet_y, SPRITE_SIZE, SPRITE_SIZE];
-                                // increment bullet counter
-                                gs.bullet_count+=1;
-
-
-                                if gs.bullet_count == 2{
-                                    gs.bullet2_x = gs.cur_x;
-                                    gs.bullet2_y = gs.cur_y;
-                                    sprites[3].to_region = [gs.bullet2_x, gs.bullet2_y, SPRITE_SIZE, SPRITE_SIZE];
-                                }  
-
-                                if gs.bullet_count == 3{
-                                    gs.bullet3_x = gs.cur_x;
-                                    gs.bullet3_y = gs.cur_y;
-                                    sprites[4].to_region = [gs.bullet3_x, gs.bullet3_y, SPRITE_SIZE, SPRITE_SIZE];
-                                }             
-
-                                
-                                }
-                        
-       
-                        // any bullets shot GOOD AND WORKING VVVVVV
-                        if gs.bullet_moving{
-                            // for sprite in &mut sprites {
-                            //     // Perform your operation here - add y vel
-                            //     let mut graivty = sprites.to_region[1] + gs.bullet_speed;
-                            //     //println!(
-                            //     "{}", graivty
-                            //     );
-                            //      // render sprite
-                            //     sprites[gs.bullet_index].to_region = [sprite.to_region[0], graivty, SPRITE_SIZE, SPRITE_SIZE];
-                            // }
-
-                            // working VVVVVVVVVVVVVVVVVVVVVVVVV
-
-                            // USING WAITING TO CHECK IF BULLET SHOT
-                            if gs.bullet_y < WINDOW_HEIGHT {
-                                
-                                //println!("RUH ROH");
-                                //cur_y = cur_y + bullet_speed;
-                                gs.bullet_y += gs.bullet_speed;
-                                //sprites[gs.bullet_index].to_region = [sprites[gs.bullet_index].to_region[0], sprites[gs.bullet_index].to_region[1] + gs.bullet_speed, SPRITE_SIZE, SPRITE_SIZE];
-                            
-                                sprites[2].to_region = [gs.bullet_x, gs.bullet_y, SPRITE_SIZE, SPRITE_SIZE];
-                                let targetx: f32 = sprites[0].to_region[0]; 
-                                
-                                if (((gs.bullet_x >= targetx-SPRITE_SIZE)&(gs.bullet_x <= targetx + SPRITE_SIZE))  & (gs.bullet_y >= WINDOW_HEIGHT-SPRITE_SIZE-50.0))  { 
-                                    hit = true;
-                                        
-                                }
-                        
-                            }
-                            ////////  VVVV
-                            if gs.bullet2_y < WINDOW_HEIGHT {
-                                
-                                //println!("RUH ROH");
-                                //cur_y = cur_y + bullet_speed;
-                                gs.bullet2_y += gs.bullet_speed;
-                                //sprites[gs.bullet_index].to_region = [sprites[gs.bullet_index].to_region[0], sprites[gs.bullet_index].to_region[1] + gs.bullet_speed, SPRITE_SIZE, SPRITE_SIZE];
-                            
-                                sprites[3].to_region = [gs.bullet2_x, gs.bullet2_y, SPRITE_SIZE, SPRITE_SIZE];
-                                let targetx: f32 = sprites[0].to_region[0]; 
-                                
-                                if (((gs.bullet2_x >= targetx-SPRITE_SIZE)&(gs.bullet2_x <= targetx + SPRITE_SIZE))  & (gs.bullet2_y >= WINDOW_HEIGHT-SPRITE_SIZE-50.0))  { 
-                                    hit2 = true;
-                                        
-                                }
-                            }   
-
-                            if gs.bullet3_y < WINDOW_HEIGHT {
-                                
-                                //println!("RUH ROH");
-                                //cur_y = cur_y + bullet_speed;
-                                gs.bullet3_y += gs.bullet_speed;
-                                //sprites[gs.bullet_index].to_region = [sprites[gs.bullet_index].to_region[0], sprites[gs.bullet_index].to_region[1] + gs.bullet_speed, SPRITE_SIZE, SPRITE_SIZE];
-                            
-                                sprites[4].to_region = [gs.bullet3_x, gs.bullet3_y, SPRITE_SIZE, SPRITE_SIZE];
-                                let targetx: f32 = sprites[0].to_region[0]; 
-                                
-                                if (((gs.bullet3_x >= targetx-SPRITE_SIZE)&(gs.bullet3_x <= targetx + SPRITE_SIZE))  & (gs.bullet3_y >= WINDOW_HEIGHT-SPRITE_SIZE-50.0))  { 
-                                    hit3 = true;
-                                        
-                                }
-                            
-                            }
-
-                            // }
-                            
-                            if (hit){
-                                gs.score += 1;
-                                gs.bullet_count -= 1;
-                                // this will reset the sprite after hitting the target
-                                gs.bullet_y = WINDOW_HEIGHT;
-                                gs.bullet_moving = false;
-
-
-                                //println!("Hit{}" , hits);
-                                
-                                let  x: f32 = rng.gen_range((0.0 ) .. 10.0 );
-                                        let sign: f32 = rng.gen_range(0.0.. 3.0);
-                                        gs.target_x = sprites[0].to_region[0];
-                                        if ((sign <1.0) & (gs.target_x < WINDOW_WIDTH - 10.0)){
-                                            gs.target_x += x;
-                                            
-                                        }  else if((sign > 1.0 )& (gs.target_x > 10.0)) {
-                                           gs.target_x -= x;
-                                        } 
-                                        sprites[0].to_region = [gs.target_x, WINDOW_HEIGHT-SPRITE_SIZE, SPRITE_SIZE, SPRITE_SIZE]; 
-                                    
-                                    }     
-
-                            if (hit2){
-                                gs.score += 1;
-                                gs.bullet_count -= 1;
-                                // this will reset the sprite after hitting the target
-                                gs.bullet2_y = WINDOW_HEIGHT;
-                                gs.bullet_moving = false;
-
-
-                                //println!("Hit{}" , hits);
-                                
-                                let  x: f32 = rng.gen_range((0.0 ) .. 10.0 );
-                                        let sign: f32 = rng.gen_range(0.0.. 3.0);
-                                        gs.target_x = sprites[0].to_region[0];
-                                        if ((sign <1.0) & (gs.target_x < WINDOW_WIDTH - 10.0)){
-                                            gs.target_x += x;
-                                            
-                                        }  else if((sign > 1.0 )& (gs.target_x > 10.0)) {
-                                           gs.target_x -= x;
-                                        } 
-                                        sprites[0].to_region = [gs.target_x, WINDOW_HEIGHT-SPRITE_SIZE, SPRITE_SIZE, SPRITE_SIZE]; 
-                                    
-                                    } 
-                            if (hit3){
-                                gs.score += 1;
-                                gs.bullet_count -= 1;
-                                // this will reset the sprite after hitting the target
-                                gs.bullet3_y = WINDOW_HEIGHT;
-                                gs.bullet_moving = false;
-
-
-                                //println!("Hit{}" , hits);
-                                
-                                
-                                    } 
-   
-                                    
-                        }
-
-
-                        }
-                    
-
-
-
-                        
-
-////////////////////////////////////////////////////////////////////////////////////////////////////
+                saved_actions = sim.actions.clone();
             }
-            else if gs.screen == 1 {
-                buffer.set_text(&mut font_system, "", Attrs::new().family(Family::Serif), Shaping::Advanced);
-                // Do we need to show new sprites?
-                if gs.waiting == false && gs.falling == false{
-                    // game restart
-                    if gs.num_stacked > 11{
-                        if !input.is_key_down(winit::event::VirtualKeyCode::Space){
-                            let new_level = gs.level + 1;
-                            let new_speed = gs.speed + 1;
-                            // Reset gs variables manually to reduce cross game variable errors
-                            gs = game_state::init_game_state();
-                            gs.screen = 1;
-                            gs.level = new_level;
-                            if game_mode == 1 {
-                                gs.drop_sprite_blocks = 5;
-                                gs.speed = new_speed;
-                            }else if game_mode == 2{
-                                gs.drop_sprite_blocks = 4;
-                                gs.speed = new_speed;
-                            }else{ // game_mode == 3
-                                gs.drop_sprite_blocks = 3;
-                                gs.speed = new_speed;
-                            }
-                            sprites = (0..gs.drop_sprite_blocks*12).map(|_| GPUSprite{
-                                to_region: 
-                                    [WINDOW_WIDTH,
-                                    WINDOW_HEIGHT,
-                                    0.0, // generate width and height to be 0 so that you can adjust later, but are now invisible
-                                    0.0], 
-                                from_region:[
-                                    0.25, 
-                                    0.0, 
-                                    0.25,
-                                    0.1],
-                            }).collect();
-
-                            // write next level text on the screen (display level for a second?)
+            // and high_scores.json, a replay's scores are its own and aren't saved
+            if sim.high_scores != saved_high_scores && options.replay.is_none() {
+                if let Err(e) = sim.high_scores.save(&high_scores_path) {
+                    log::error!("{}", e);
+                }
+                saved_high_scores = sim.high_scores.clone();
+            }
+            // let the IME pop up only while typing
+            if sim.wants_text() != ime_allowed {
+                ime_allowed = sim.wants_text();
+                window.set_ime_allowed(ime_allowed);
+            }
+            if let Some(watcher) = &mut watcher {
+                let changed = watcher.changed();
+                if changed.iter().any(|path| path.ends_with("shader.wgsl")) {
+                    match reload_sprite_pipeline(&device, &pipeline_layout, &assets.path(SHADER_PATH), swapchain_format) {
+                        Ok(pipeline) => {
+                            render_pipeline = pipeline;
+                            shader_error = None;
+                            log::info!("reloaded {}", SHADER_PATH);
                         }
-                    }else if gs.drop_sprite_blocks == 0{
-                        gs = game_state::init_game_state();
-                        gs.screen = 3; // go to game over screen
-                    }
-                    let mut i:usize = gs.sprites_used;
-                    // XPOS OF LEFTMOST SPRITE
-                    let x_pos = rng.gen_range(0..WINDOW_WIDTH as usize-(SPRITE_SIZE as usize*gs.drop_sprite_blocks));
-                    // chooe a random color on the sprite sheet for this row that will drop
-                    let color_loc: (f32, f32) = (
-                        0.25 + rng.gen_range(0..2) as f32*0.25,
-                        0.0 + rng.gen_range(0..10) as f32*0.1);
-                    while i < gs.drop_sprite_blocks + gs.sprites_used{
-                        sprites[i].to_region = [
-                            x_pos as f32+(((i-gs.sprites_used)*64) as f32), 
-                            WINDOW_HEIGHT - SPRITE_SIZE, 
-                            SPRITE_SIZE, 
-                            SPRITE_SIZE];
-                        sprites[i].from_region = [
-                            color_loc.0, 
-                            color_loc.1,
-                            0.25,
-                            0.1];
-                        i += 1;
-                    }
-                    gs.sprites_used += gs.drop_sprite_blocks;
-                    gs.waiting = true;
-                // Do we need to animate falling sprite
-                }else if gs.falling == true{
-                    let mut still_falling = false;
-                    for sprite in &mut sprites {
-                        let cur_y = sprite.to_region[1];
-                        // if it has not yet fallen below the level it will fall to, keep falling
-                        if cur_y >= 0.0 + gs.num_stacked as f32*SPRITE_SIZE && cur_y < WINDOW_HEIGHT{
-                            still_falling = true;
-                            sprite.to_region = [sprite.to_region[0], cur_y - gs.speed as f32/2.0, SPRITE_SIZE, SPRITE_SIZE];
+                        Err(e) => {
+                            log::error!("{}", e);
+                            shader_error = Some(e);
                         }
                     }
-                    if !still_falling{
-                        gs.falling = false;
-                        gs.num_stacked += 1;
-                    }
-                    // We are waiting for space to be clicked, and then acting on it
-                }else{
-                    if input.is_key_down(winit::event::VirtualKeyCode::Space){
-                        buffer.set_text(&mut font_system, "", Attrs::new().family(Family::Serif), Shaping::Advanced);
-                        let mut left_edge = WINDOW_WIDTH;
-                        let mut right_edge = 0.0;
-                        for sprite in &mut sprites {
-                            if sprite.to_region[1] == WINDOW_HEIGHT-SPRITE_SIZE{
-                                if sprite.to_region[0] < left_edge {
-                                    left_edge = sprite.to_region[0];
-                                }
-                                if sprite.to_region[0] > right_edge {
-                                    right_edge = sprite.to_region[0];
-                                }
-                                ////println!("left: {} right: {}", left_edge, right_edge);
-                                if sprite.to_region[0] < (gs.left_border - SPRITE_SIZE/2.0){
-                                    sprite.to_region = [
-                                        100.0, 
-                                        WINDOW_HEIGHT, 
-                                        0.0, 
-                                        0.0];
-                                    gs.drop_sprite_blocks -= 1;
-                                }
-                                if sprite.to_region[0] > (gs.right_border + SPRITE_SIZE/2.0){
-                                    sprite.to_region = [
-                                        100.0, 
-                                        WINDOW_HEIGHT, 
-                                        0.0, 
-                                        0.0];
-                                    gs.drop_sprite_blocks -= 1;
-                                }
+                }
+                if changed.iter().any(|path| path.starts_with(assets.path(CONTENT_PATH))) {
+                    // everything in content/ is small, just load all of it again
+                    assets.clear_errors();
+                    assets.reload_textures(&device, &queue);
+                    let atlas = assets.atlas(assets::SPRITE_ATLAS);
+                    let font = assets.font(assets::FONT);
+                    match (atlas, font) {
+                        (Some(atlas), Some(font)) if assets.errors().is_empty() => {
+                            // the .json might point at a different PNG now
+                            let tex_sprite = assets.texture(&device, &queue, &atlas.image_path);
+                            let tex_font = assets.texture(&device, &queue, &font.image_path);
+                            texture_bind_group = create_texture_bind_group(&device, &texture_bind_group_layout, assets.get(tex_sprite), &sampler_sprite);
+                            font_bind_group = create_texture_bind_group(&device, &texture_bind_group_layout, assets.get(tex_font), &sampler_sprite);
+                            // screens pick up changed regions the next time they start (esc restarts)
+                            sim.atlas = atlas;
+                            bitmap_font = Some(font);
+                            content_error = None;
+                            log::info!("reloaded {}", CONTENT_PATH);
+                            // everything's there now, the game can start
+                            if missing_assets {
+                                missing_assets = false;
+                                sim.go_to(Box::new(TitleScene::new()), hud_font(&mut bitmap_font, &mut font_system, input.bitmap_font()));
                             }
                         }
-                        // now update the edges of the game state for the next frame
-                        if left_edge > gs.left_border {
-                            gs.left_border = left_edge;
-                        }
-                        if right_edge < gs.right_border {
-                            gs.right_border = right_edge;
-                        }
-
-
-                        gs.waiting = false;
-                        gs.falling = true;
-                    }else{
-                        //ANIMATE BACK AND FORTH
-                        // direction = true when going left
-                        // consider adding active field to sprites
-                        let mut delta = gs.speed as f32;
-                        if gs.direction == true{
-                            delta = gs.speed as f32 * (-1.0);
-                        }
-                        if gs.num_stacked == 0{
-                            let text_1 = "Level: ";
-                            let text = text_1.to_owned() + &gs.level.to_string();
-                            buffer.set_text(&mut font_system, &text, Attrs::new().family(Family::Serif), Shaping::Advanced);
-                        }
-
-                        for sprite in &mut sprites {
-                            if sprite.to_region[1] == WINDOW_HEIGHT - SPRITE_SIZE{
-                                let cur_x = sprite.to_region[0];
-                                if cur_x >= 960.0 - delta{
-                                    gs.direction = true;
-                                }else if cur_x < 0.0 + delta{
-                                    gs.direction = false
-                                }
-                                sprite.to_region = [cur_x + delta, WINDOW_HEIGHT - SPRITE_SIZE, SPRITE_SIZE, SPRITE_SIZE];
-                            }                    
+                        _ => {
+                            let errors: Vec<String> = assets.errors().iter().map(|e| e.to_string()).collect();
+                            log::error!("{}", errors.join("\n"));
+                            content_error = Some(errors.join("\n"));
                         }
                     }
-
-                    // SCREEN == 5 -> SPACE GAME LOOP
-                }
-            } else if gs.screen == 2 {   
-                // Block falling game setup screen
-                gs = game_state::init_game_state();
-                gs.screen = 2;
-                // reset sprites
-                sprites = (0..gs.drop_sprite_blocks*12).map(|_| GPUSprite{
-                    to_region: [WINDOW_WIDTH,WINDOW_HEIGHT,0.0, 0.0], 
-                    from_region:[0.25, 0.0, 0.25,0.1],}).collect();
-                // Text for setup 
-                buffer.set_text(&mut font_system, "Press a key to choose your difficulty level:\n1:EASY\n2:INTERMEDIATE\n3:ADVANCED", Attrs::new().family(Family::Serif), Shaping::Advanced);
-                // input logic
-                if input.is_key_down(winit::event::VirtualKeyCode::Key1){
-                    game_mode = 1;
-                    gs.screen = 1;
-                }else if input.is_key_down(winit::event::VirtualKeyCode::Key2){
-                    game_mode = 2;
-                    gs.screen = 1;
-                }else if input.is_key_down(winit::event::VirtualKeyCode::Key3){
-                    game_mode = 3;
-                    gs.screen = 1;
-                }
-                if game_mode == 1 {
-                    gs.drop_sprite_blocks = 5;
-                    gs.speed = 4;
-                }else if game_mode == 2{
-                    gs.drop_sprite_blocks = 4;
-                    gs.speed = 6;
-                }else{ // game_mode == 3
-                    gs.drop_sprite_blocks = 3;
-                    gs.speed = 10;
-                }
-
-            } else if gs.screen == 3{
-                // reset sprites
-                sprites = (0..gs.drop_sprite_blocks*12).map(|_| GPUSprite{
-                    to_region: [WINDOW_WIDTH,WINDOW_HEIGHT,0.0, 0.0], 
-                    from_region:[0.25, 0.0, 0.25,0.1],}).collect();
-                // Block falling game over screen
-                buffer.set_text(&mut font_system, "GAME OVER!!!\nPress c to continue playing this game\nPress esc for title screen", Attrs::new().family(Family::Serif), Shaping::Advanced);
-                if input.is_key_down(winit::event::VirtualKeyCode::C){
-                    gs = game_state::init_game_state();
-                    gs.screen = 2;
                 }
-            }else if gs.screen == 4{
-
-            //}else if gs.screen == 5{
-                // buffer.set_text(&mut font_system, "", Attrs::new().family(Family::Serif), Shaping::Advanced);
-                // // space game
-                // //println!("GAME 2!!!!");
-                // gs.screen = 5;
-
-                // if gs.start{
-                // // target sprite
-                // sprites[0].to_region = [
-                //     500.0, 
-                //     WINDOW_HEIGHT - SPRITE_SIZE, 
-                //     SPRITE_SIZE, 
-                //     SPRITE_SIZE];
-                // sprites[0].from_region = [
-                //     0.75, 
-                //     0.0,
-                //     0.25,
-                //     0.1];
-
-                // // ship sprite VVV
-                // sprites[1].to_region = [
-                //     gs.cur_x, 
-                //     gs.cur_y, 
-                //     SPRITE_SIZE, 
-                //     SPRITE_SIZE];
-                // sprites[1].from_region = [
-                //     0.75, 
-                //     0.9,
-                //     0.25,
-                //     0.1];
-
-                // // Bullet SPrite - initially invisible
-                // sprites[2].to_region = [
-                //     sprites[1].to_region[0], 
-                //     sprites[1].to_region[1], 
-                //     // initially invisible
-                //     0.0, 
-                //     0.0];
-                // sprites[2].from_region = [
-                //     0.5, 
-                //     0.9,
-                //     0.25,
-                //     0.1];
-
-                // // checks left and right movement
-                // if input.is_key_down(winit::event::VirtualKeyCode::Left){
-                //     //println!("Left");
-                //     gs.cur_x -= 6.0;
-                //     sprites[1].to_region = [gs.cur_x, 0.0, SPRITE_SIZE, SPRITE_SIZE];
-                //     //println!("{}", gs.cur_x)
-                // }
-
-                // else if input.is_key_down(winit::event::VirtualKeyCode::Right){
-                //     //println!("Right");
-                //     gs.cur_x += 6.0;
-                //     sprites[1].to_region = [gs.cur_x, 0.0, SPRITE_SIZE, SPRITE_SIZE];
-                //     //println!("{}", gs.cur_x)
-                // }
-
-                // else if input.is_key_down(winit::event::VirtualKeyCode::Space){
-                //     //println!("SHOOTING");
-                //     // USING WAITING TO CHECK IF BULLET SHOT
-                //     gs.bullet_moving = true;
-                //     gs.bullet_x = gs.cur_x;
-                //     gs.bullet_y = gs.cur_y;
-                //     sprites[2].to_region = [gs.bullet_x, gs.bullet_y, SPRITE_SIZE, SPRITE_SIZE];
-
-
-                // }
-
-                // // if gs.bullet_moving{
-
-                // //     //println!("SHOOTING");
-                // //     // USING WAITING TO CHECK IF BULLET SHOT
-                // //     if gs.bullet_y < WINDOW_HEIGHT {
-                // //     //cur_y = cur_y + bullet_speed;
-                // //     gs.bullet_y += gs.bullet_speed;
-                // //     //println!("{}", gs.cur_y);
-                // //     sprites[2].to_region = [gs.bullet_x, gs.bullet_y, SPRITE_SIZE, SPRITE_SIZE];
-
-                // //     }
-                // //     else{
-                // //         gs.bullet_moving = false;
-                // //     }
-                // // }
-
-
-                // }
-            
-            }else if gs.screen == 6{
-
             }
 
-            // Text rendering
-            text_renderer.prepare(
-                &device,
-                &queue,
-                &mut font_system,
-                &mut atlas,
-                Resolution {
-                    width: config.width,
-                    height: config.height,
-                },
-                [TextArea {
-                    buffer: &buffer,
-                    left: 150.0,
-                    top: 200.0,
-                    scale: 1.0,
-                    bounds: TextBounds {
-                        left: 0,
-                        top: 0,
-                        right: 50000,
-                        bottom: 100000,
+            // Text rendering, placed and scaled the same way as the sprites so they stay lined up
+            let mut hud = sim.hud();
+            let errors: Vec<&str> = [&shader_error, &content_error].into_iter().flatten().map(|e| e.as_str()).collect();
+            if !errors.is_empty() {
+                hud.set("reload_error", TextBlock::new(errors.join("\n"), Anchor::BottomLeft).offset(10.0, -10.0).size(16.0).color([255, 80, 80, 255]));
+            }
+            let text_offset = sim.interpolated_text_offset(timestep.alpha());
+            // the pixel font can only be used if it loaded
+            let bitmap = bitmap_font.as_ref().filter(|_| input.bitmap_font());
+            if let Some(bitmap_font) = bitmap {
+                let glyphs = bitmap_font.layout_hud(&hud, text_offset);
+                text_batch.upload(&device, &queue, &sprite_bind_group_layout, &buffer_hud_camera, &glyphs);
+            } else {
+                text_layer.update(&mut font_system, &hud, text_offset);
+                text_renderer.prepare(
+                    &device,
+                    &queue,
+                    &mut font_system,
+                    &mut atlas,
+                    Resolution {
+                        width: config.width,
+                        height: config.height,
                     },
-                    default_color: Color::rgb(255, 255, 255),
-                }],
-                &mut cache,
-            ).unwrap();
-
+                    text_layer.text_areas(&viewport),
+                    &mut cache,
+                ).unwrap();
+            }
             // Remember this from before?
             //input.next_frame();
+            // draw sprites part of the way to the next update so motion is smooth at any frame rate
+            let camera = sim.interpolated_camera(timestep.alpha());
             queue.write_buffer(&buffer_camera, 0, bytemuck::bytes_of(&camera));
-            queue.write_buffer(&buffer_sprite, 0, bytemuck::cast_slice(&sprites));
+            let mut sprites = sim.interpolated_sprites(timestep.alpha());
+            // the backdrop goes behind all of it, there's none until the sprite sheet has loaded
+            if let Ok(region) = sim.atlas.region("background") {
+                sprites.push(sprite::background(&camera, region));
+            }
+            sprite::sort_by_depth(&mut sprites);
+            sprite_batch.upload(&device, &queue, &sprite_bind_group_layout, &buffer_camera, &sprites);
 
             let frame = surface
                 .get_current_texture()
@@ -1284,7 +646,8 @@ let sprite_bind_group_layout =
                 let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                     label: None,
                     color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &view,
+                        // the offscreen texture, not the window
+                        view: post.scene_view(),
                         resolve_target: None,
                         ops: wgpu::Operations {
                             load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
@@ -1295,30 +658,43 @@ let sprite_bind_group_layout =
                     // timestamp_writes: None,
                     // occlusion_query_set: None,
                 });
-                text_renderer.render(&atlas, &mut rpass).unwrap();
+                // sprites only go in the game's part of the window
+                rpass.set_viewport(viewport.x, viewport.y, viewport.width, viewport.height, 0.0, 1.0);
                 rpass.set_pipeline(&render_pipeline);
-                rpass.set_bind_group(0, &sprite_bind_group, &[]);
                 rpass.set_bind_group(1, &texture_bind_group, &[]);
-
-                // draw two triangles per sprite, and sprites-many sprites.
-                // this uses instanced drawing, but it would also be okay
-                // to draw 6 * sprites.len() vertices and use modular arithmetic
-                // to figure out which sprite we're drawing, instead of the instance index.
-                rpass.draw(0..6, 0..(sprites.len() as u32));
+                sprite_batch.draw(&mut rpass);
+                // the hud goes on top
+                if bitmap.is_some() {
+                    // glyph sprites are in game pixels just like the other sprites
+                    rpass.set_bind_group(1, &font_bind_group, &[]);
+                    text_batch.draw(&mut rpass);
+                } else {
+                    // glyphon text is already placed in window coordinates
+                    rpass.set_viewport(0.0, 0.0, config.width as f32, config.height as f32, 0.0, 1.0);
+                    text_renderer.render(&atlas, &mut rpass).unwrap();
+                }
             } 
-            
+            // then onto the window through the effects
+            post.run(&mut encoder, &queue, &view, &settings.effects(sim.flash), sim.flash);
 
             queue.submit(Some(encoder.finish()));
             frame.present();
             
             window.request_redraw();
-            input.next_frame();
             atlas.trim();
         },
         Event::WindowEvent {
             event: WindowEvent::CloseRequested,
             ..
-        } => *control_flow = ControlFlow::Exit,
+        } => {
+            if let (Some(recording), Some(path)) = (&recording, &options.record) {
+                match recording.save(path) {
+                    Ok(()) => log::info!("saved replay to {}", path.display()),
+                    Err(e) => log::error!("{}", e),
+                }
+            }
+            *control_flow = ControlFlow::Exit
+        },
         _ => {}
     }
     window.request_redraw();
@@ -1332,15 +708,71 @@ let sprite_bind_group_layout =
 
 
 
+// Flags for running the game (--snapshot is handled on its own in main)
+#[derive(Default)]
+struct Options {
+    // --dev: reload the shader and content/ when they change
+    dev: bool,
+    // --record <file>: save everything the player does, to play it back later
+    record: Option<PathBuf>,
+    // --replay <file>: play a recorded run instead of reading the keyboard and mouse
+    replay: Option<PathBuf>,
+    // --seed <number>: start the random numbers from here instead of somewhere random
+    seed: Option<u64>,
+}
+
+impl Options {
+    fn from_args(args: &[String]) -> Options {
+        let mut options = Options::default();
+        let mut args = args.iter().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--dev" => options.dev = true,
+                "--record" => options.record = Some(PathBuf::from(args.next().expect("--record needs a file to save to"))),
+                "--replay" => options.replay = Some(PathBuf::from(args.next().expect("--replay needs a file to play"))),
+                "--seed" => {
+                    let seed = args.next().expect("--seed needs a number");
+                    options.seed = Some(seed.parse().unwrap_or_else(|_| panic!("--seed needs a number, not {}", seed)));
+                }
+                other => panic!("unknown argument {}", other),
+            }
+        }
+        options
+    }
+}
+
+// true when a key was just pressed, not when it's held down and repeating
+fn pressed_once(key_ev: &KeyboardInput, input: &input::Input, key: VirtualKeyCode) -> bool {
+    key_ev.state == ElementState::Pressed
+        && key_ev.virtual_keycode == Some(key)
+        && !input.is_key_down(key)
+}
+
+// what the hud is drawn in, the pixel font when it's switched on and it loaded, otherwise the system fonts
+fn hud_font<'a>(bitmap_font: &'a mut Option<bitmap_font::BitmapFont>, font_system: &'a mut FontSystem, use_bitmap: bool) -> &'a mut dyn MeasureText {
+    match bitmap_font {
+        Some(font) if use_bitmap => font,
+        _ => font_system,
+    }
+}
+
 // Main is just going to configure an event loop, open a window, set up logging, and kick off our `run` function.
 fn main() {
+    // Headless snapshot without opening a window or touching the GPU:
+    // hello-triangle --snapshot <title|block|space> <out.png>
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() == 4 && args[1] == "--snapshot" {
+        raster::write_snapshot(&args[2], &args[3]).expect("Couldn't write snapshot");
+        return;
+    }
+    let options = Options::from_args(&args);
     let event_loop = EventLoop::new();
     let window = winit::window::Window::new(&event_loop).unwrap();
     #[cfg(not(target_arch = "wasm32"))]
     {
         env_logger::init();
         // On native, we just want to wait for `run` to finish.
-        pollster::block_on(run(event_loop, window));
+        pollster::block_on(run(event_loop, window, options));
     }
     #[cfg(target_arch = "wasm32")]
     {
@@ -1358,48 +790,91 @@ fn main() {
             })
             .expect("couldn't append canvas to document body");
         // Now we use the browser's runtime to spawn our async run function.
-        wasm_bindgen_futures::spawn_local(run(event_loop, window));
+        wasm_bindgen_futures::spawn_local(run(event_loop, window, options));
     }
 
     
 }
 
 
-// AsRef means we can take as parameters anything that cheaply converts into a Path,
-// for example an &str.
-fn load_texture(
-    path: impl AsRef<std::path::Path>,
-    label: Option<&str>,
+// same bind group layout for every texture: the texture, then the sampler
+fn create_texture_bind_group(
     device: &wgpu::Device,
-    queue: &wgpu::Queue,
-) -> Result<(wgpu::Texture, image::RgbaImage), image::ImageError> {
-    // This ? operator will return the error if there is one, unwrapping the result otherwise.
-    let img = image::open(path.as_ref())?.to_rgba8();
-    let (width, height) = img.dimensions();
-    let size = wgpu::Extent3d {
-        width,
-        height,
-        depth_or_array_layers: 1,
-    };
-    let texture = device.create_texture(&wgpu::TextureDescriptor {
-        label,
-        size,
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Rgba8UnormSrgb,
-        usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-        view_formats: &[],
+    layout: &wgpu::BindGroupLayout,
+    texture: &wgpu::Texture,
+    sampler: &wgpu::Sampler,
+) -> wgpu::BindGroup {
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: None,
+        layout,
+        entries: &[
+            // One for the texture, one for the sampler
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
+
+fn create_sprite_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    source: &str,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    // Remember, shader programs are things we compile for
+    // our GPU so that it can compute vertices and colorize fragments.
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        // Cow is a "copy on write" wrapper that abstracts over owned or borrowed memory.
+        // Here we just need to use it since wgpu wants "some text" to compile a shader from.
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
     });
-    queue.write_texture(
-        texture.as_image_copy(),
-        &img,
-        wgpu::ImageDataLayout {
-            offset: 0,
-            bytes_per_row: Some(4 * width),
-            rows_per_image: Some(height),
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
         },
-        size,
-    );
-    Ok((texture, img))
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            // alpha blend sprites over what's behind them, so they can be faded or see-through
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    })
+}
+
+// read the shader from disk again and rebuild the pipeline. Mistakes in the WGSL come back as an
+// Err with the compiler's message instead of wgpu panicking
+fn reload_sprite_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    path: &Path,
+    format: wgpu::TextureFormat,
+) -> Result<wgpu::RenderPipeline, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    // anything invalid while building the pipeline is caught here instead of going to the panic handler
+    device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let pipeline = create_sprite_pipeline(device, layout, &source, format);
+    match pollster::block_on(device.pop_error_scope()) {
+        Some(e) => Err(e.to_string()),
+        None => Ok(pipeline),
+    }
 }
@@ -0,0 +1,70 @@
+use winit::event::MouseButton;
+
+use crate::hud::{Align, Anchor, Hud, TextBlock, TextStyle};
+use crate::input::Input;
+use crate::scene::SceneContext;
+use crate::WINDOW_HEIGHT;
+
+// the item under the mouse
+const HOVER_COLOR: [u8;4] = [255, 215, 0, 255];
+
+// A column of choices in the middle of the screen that can be clicked as well as picked with keys.
+// Every item is its own hud block (ex. "menu_0", "menu_1"), and what the mouse is over is worked
+// out from those same blocks measured in the font they're drawn in
+pub struct Menu {
+    name: &'static str,
+    items: Vec<String>,
+    // how far below the middle of the screen the column is centered
+    offset_y: f32,
+    style: TextStyle,
+    // which item the mouse is over
+    pub hovered: Option<usize>,
+}
+
+impl Menu {
+    pub fn new(name: &'static str, items: &[&str]) -> Menu {
+        let items = items.iter().map(|&item| item.to_owned()).collect();
+        Menu { name, items, offset_y: 0.0, style: TextStyle { align: Align::Center, ..TextStyle::default() }, hovered: None }
+    }
+
+    pub fn offset(mut self, y: f32) -> Menu {
+        self.offset_y = y;
+        self
+    }
+
+    fn block_name(&self, i: usize) -> String {
+        format!("{}_{}", self.name, i)
+    }
+
+    // the hud block for one item, colored for whether it's hovered
+    fn block(&self, i: usize) -> TextBlock {
+        let line_height = self.style.line_height();
+        let middle = (self.items.len() as f32 - 1.0) / 2.0;
+        let color = if self.hovered == Some(i) { HOVER_COLOR } else { self.style.color };
+        let y = self.offset_y + (i as f32 - middle) * line_height;
+        TextBlock::new(self.items[i].as_str(), Anchor::Center).offset(0.0, y).size(self.style.size).color(color).align(self.style.align)
+    }
+
+    // work out what the mouse is over, returns the item that was clicked this update.
+    // A click is letting go of the button over an item, so the press can't carry over into the next screen
+    pub fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Option<usize> {
+        let mouse = input.mouse_pos();
+        // the hud measures y down from the top
+        let [x, y] = [mouse.x as f32, WINDOW_HEIGHT - mouse.y as f32];
+        self.hovered = (0..self.items.len()).find(|&i| {
+            let [left, top, width, height] = self.block(i).layout_bounds(ctx.font, *ctx.text_offset);
+            x >= left && x < left + width && y >= top && y < top + height
+        });
+        if input.is_mouse_released(MouseButton::Left) {
+            self.hovered
+        } else {
+            None
+        }
+    }
+
+    pub fn draw(&self, hud: &mut Hud) {
+        for i in 0..self.items.len() {
+            hud.set(&self.block_name(i), self.block(i));
+        }
+    }
+}
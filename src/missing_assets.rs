@@ -0,0 +1,34 @@
+use crate::game_state::GameState;
+use crate::hud::{Anchor, Hud, TextBlock};
+use crate::input::Input;
+use crate::scene::{Scene, SceneContext, Transition};
+
+// Shown instead of the title when some of the game's files couldn't be loaded.
+// There's nothing to play without them, so esc doesn't leave this screen
+pub struct MissingAssetsScene {
+    // one line per file
+    pub errors: Vec<String>,
+    // where the files were looked for
+    pub root: String,
+}
+
+impl Scene for MissingAssetsScene {
+    fn enter(&mut self, ctx: &mut SceneContext) {
+        // nothing to draw but the text
+        ctx.sprites.clear();
+    }
+
+    fn update(&mut self, _ctx: &mut SceneContext, _input: &Input) -> Transition {
+        Transition::None
+    }
+
+    fn draw(&self, _gs: &GameState, hud: &mut Hud) {
+        hud.set("title", TextBlock::new("Couldn't load the game's files", Anchor::TopLeft).offset(40.0, 40.0).size(40.0).color([255, 80, 80, 255]));
+        hud.set("errors", TextBlock::new(self.errors.join("\n"), Anchor::TopLeft).offset(40.0, 120.0).size(18.0));
+        hud.set("help", TextBlock::new(format!("Looked in {}\nSet ASSET_ROOT to the folder with content/ in it, then restart", self.root), Anchor::BottomLeft).offset(40.0, -40.0).size(18.0));
+    }
+
+    fn escapable(&self) -> bool {
+        false
+    }
+}
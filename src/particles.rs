@@ -0,0 +1,239 @@
+use rand::Rng;
+
+use crate::sprite::{self, GPUSprite};
+
+// most particles alive at once, new ones are dropped past this
+const MAX_PARTICLES: usize = 2000;
+
+// One bit of confetti/debris. Speeds are in world units per second.
+struct Particle {
+    pos: [f32;2],
+    vel: [f32;2],
+    // seconds since it spawned, and how many it lives for
+    age: f32,
+    lifetime: f32,
+    rotation: f32,
+    spin: f32,
+}
+
+impl Particle {
+    // 0 when it spawns, 1 when it dies
+    fn life(&self) -> f32 {
+        (self.age / self.lifetime).min(1.0)
+    }
+}
+
+// Spawns particles at a point. Each emitter owns the particles it spawned, so they
+// all share its look (region, color and size over life, gravity).
+#[derive(Clone, Copy, Debug)]
+pub struct Emitter {
+    pub pos: [f32;2],
+    // particles per second while the emitter is running
+    pub rate: f32,
+    // seconds the emitter keeps spawning for, 0 for bursts that only spawn once
+    pub duration: f32,
+    // seconds each particle lives (min, max)
+    pub lifetime: [f32;2],
+    // direction particles fly in (radians, 0 is right, counterclockwise) and how far either side of it they can go
+    pub angle: f32,
+    pub spread: f32,
+    // world units per second (min, max)
+    pub speed: [f32;2],
+    // pulls particles down, world units per second per second
+    pub gravity: f32,
+    // color and size are blended from start to end over each particle's life
+    pub start_color: [f32;4],
+    pub end_color: [f32;4],
+    pub start_size: f32,
+    pub end_size: f32,
+    // radians per second either way (picked at random per particle)
+    pub spin: f32,
+    // sprite sheet region the particles are drawn with
+    pub region: [f32;4],
+}
+
+impl Default for Emitter {
+    fn default() -> Emitter {
+        Emitter {
+            pos: [0.0, 0.0],
+            rate: 0.0,
+            duration: 0.0,
+            lifetime: [0.5, 1.0],
+            angle: std::f32::consts::FRAC_PI_2,
+            spread: std::f32::consts::PI,
+            speed: [50.0, 150.0],
+            gravity: 0.0,
+            start_color: sprite::WHITE,
+            end_color: [1.0, 1.0, 1.0, 0.0],
+            start_size: 8.0,
+            end_size: 8.0,
+            spin: 0.0,
+            region: [0.25, 0.0, 0.25, 0.1],
+        }
+    }
+}
+
+// Ready made effects
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Burst {
+    // a bullet hit the target in the space game
+    Hit,
+    // a block hanging off the tower got cut off
+    BlockTrimmed,
+    // a block tower reached the top
+    LevelComplete,
+}
+
+impl Burst {
+    // how many particles it spawns right away, and the emitter they come from
+    fn emitter(self, pos: [f32;2], region: [f32;4]) -> (usize, Emitter) {
+        match self {
+            Burst::Hit => (24, Emitter {
+                pos,
+                lifetime: [0.2, 0.5],
+                speed: [150.0, 400.0],
+                start_color: [1.0, 0.9, 0.4, 1.0],
+                end_color: [1.0, 0.2, 0.0, 0.0],
+                start_size: 10.0,
+                end_size: 2.0,
+                region,
+                ..Emitter::default()
+            }),
+            Burst::BlockTrimmed => (12, Emitter {
+                pos,
+                lifetime: [0.4, 0.8],
+                spread: 1.2,
+                speed: [60.0, 200.0],
+                gravity: 600.0,
+                end_color: [1.0, 1.0, 1.0, 0.0],
+                start_size: 12.0,
+                end_size: 6.0,
+                spin: 8.0,
+                region,
+                ..Emitter::default()
+            }),
+            // a fountain of confetti for a second instead of all at once
+            Burst::LevelComplete => (0, Emitter {
+                pos,
+                rate: 150.0,
+                duration: 1.0,
+                lifetime: [1.0, 1.8],
+                spread: 0.5,
+                speed: [450.0, 700.0],
+                gravity: 500.0,
+                end_color: [1.0, 1.0, 1.0, 0.0],
+                start_size: 14.0,
+                end_size: 10.0,
+                spin: 6.0,
+                region,
+                ..Emitter::default()
+            }),
+        }
+    }
+}
+
+struct Running {
+    emitter: Emitter,
+    // seconds left to keep spawning
+    time_left: f32,
+    // fraction of a particle owed from the last update
+    owed: f32,
+    particles: Vec<Particle>,
+}
+
+// All the live emitters and their particles. They're simulated on the CPU and drawn
+// as ordinary sprites on top of whatever the screen put in the sprite list.
+#[derive(Default)]
+pub struct ParticleSystem {
+    running: Vec<Running>,
+}
+
+impl ParticleSystem {
+    // start an emitter, it keeps going for emitter.duration seconds and goes away once its particles are gone
+    pub fn add(&mut self, emitter: Emitter) {
+        self.running.push(Running { emitter, time_left: emitter.duration, owed: 0.0, particles: vec![] });
+    }
+
+    // one of the presets at pos, drawn with region from the sprite sheet
+    pub fn burst(&mut self, burst: Burst, pos: [f32;2], region: [f32;4], rng: &mut impl Rng) {
+        let (count, emitter) = burst.emitter(pos, region);
+        let room = MAX_PARTICLES.saturating_sub(self.count());
+        self.add(emitter);
+        let running = self.running.last_mut().unwrap();
+        for _ in 0..count.min(room) {
+            Self::spawn(running, rng);
+        }
+    }
+
+    // get rid of everything, for when the screen changes
+    pub fn clear(&mut self) {
+        self.running.clear();
+    }
+
+    fn count(&self) -> usize {
+        self.running.iter().map(|r| r.particles.len()).sum()
+    }
+
+    fn spawn(running: &mut Running, rng: &mut impl Rng) {
+        let e = &running.emitter;
+        let angle = e.angle + rng.gen_range(-1.0..=1.0) * e.spread;
+        let speed = rng.gen_range(e.speed[0]..=e.speed[1]);
+        running.particles.push(Particle {
+            pos: e.pos,
+            vel: [angle.cos() * speed, angle.sin() * speed],
+            age: 0.0,
+            lifetime: rng.gen_range(e.lifetime[0]..=e.lifetime[1]),
+            rotation: 0.0,
+            spin: rng.gen_range(-1.0..=1.0) * e.spin,
+        });
+    }
+
+    // move everything along by dt seconds and spawn what the emitters owe
+    pub fn update(&mut self, dt: f32, rng: &mut impl Rng) {
+        let mut room = MAX_PARTICLES.saturating_sub(self.count());
+        for running in self.running.iter_mut() {
+            if running.time_left > 0.0 {
+                running.time_left -= dt;
+                running.owed += running.emitter.rate * dt;
+                while running.owed >= 1.0 {
+                    running.owed -= 1.0;
+                    if room > 0 {
+                        room -= 1;
+                        Self::spawn(running, rng);
+                    }
+                }
+            }
+            let gravity = running.emitter.gravity;
+            for p in running.particles.iter_mut() {
+                p.age += dt;
+                p.vel[1] -= gravity * dt;
+                p.pos[0] += p.vel[0] * dt;
+                p.pos[1] += p.vel[1] * dt;
+                p.rotation += p.spin * dt;
+            }
+            running.particles.retain(|p| p.age < p.lifetime);
+        }
+        self.running.retain(|r| r.time_left > 0.0 || !r.particles.is_empty());
+    }
+
+    // add a sprite for every live particle to the end of sprites
+    pub fn append_sprites(&self, sprites: &mut Vec<GPUSprite>) {
+        for running in &self.running {
+            let e = &running.emitter;
+            for p in &running.particles {
+                let t = p.life();
+                let size = e.start_size + (e.end_size - e.start_size) * t;
+                let color = std::array::from_fn(|c| e.start_color[c] + (e.end_color[c] - e.start_color[c]) * t);
+                sprites.push(GPUSprite {
+                    // pos is the middle of the particle
+                    to_region: [p.pos[0] - size/2.0, p.pos[1] - size/2.0, size, size],
+                    from_region: e.region,
+                    color,
+                    rotation: p.rotation,
+                    depth: sprite::DEPTH_EFFECTS,
+                    pivot: [0.5, 0.5],
+                });
+            }
+        }
+    }
+}
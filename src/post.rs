@@ -0,0 +1,219 @@
+use std::borrow::Cow;
+
+// Full-screen effects, each one is a fragment shader in post.wgsl
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    // old TV lines
+    Scanlines,
+    // darker corners
+    Vignette,
+    // bright things glow
+    Bloom,
+    // the screen fades to white (how much comes from the game, see Simulation::flash)
+    Flash,
+}
+
+impl Effect {
+    const ALL: [Effect; 4] = [Effect::Scanlines, Effect::Vignette, Effect::Bloom, Effect::Flash];
+
+    fn entry_point(self) -> &'static str {
+        match self {
+            Effect::Scanlines => "fs_scanlines",
+            Effect::Vignette => "fs_vignette",
+            Effect::Bloom => "fs_bloom",
+            Effect::Flash => "fs_flash",
+        }
+    }
+}
+
+// uniforms for post.wgsl, has to match Params in there
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+struct PostParams {
+    resolution: [f32;2],
+    flash: f32,
+    _padding: f32,
+}
+
+// A texture effects can read from and draw into
+struct Target {
+    view: wgpu::TextureView,
+    // reads this texture
+    bind_group: wgpu::BindGroup,
+}
+
+// The game is drawn into an offscreen texture (scene_view), then run draws it to the window
+// through whichever effects are turned on, ping-ponging between two textures in between.
+pub struct PostProcess {
+    format: wgpu::TextureFormat,
+    layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    params: wgpu::Buffer,
+    copy: wgpu::RenderPipeline,
+    pipelines: Vec<(Effect, wgpu::RenderPipeline)>,
+    // targets[0] is where the scene gets drawn
+    targets: [Target; 2],
+    width: u32,
+    height: u32,
+}
+
+impl PostProcess {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat, width: u32, height: u32) -> PostProcess {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("post"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("post.wgsl"))),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post"),
+            entries: &[
+                // the picture so far
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                // PostParams
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = |entry_point: &str| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point,
+                targets: &[Some(format.into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let copy = pipeline("fs_copy");
+        let pipelines = Effect::ALL.iter().map(|&effect| (effect, pipeline(effect.entry_point()))).collect();
+        // effects that look at neighboring pixels want smooth sampling and no wrapping around the edges
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("post params"),
+            size: std::mem::size_of::<PostParams>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let (width, height) = (width.max(1), height.max(1));
+        let targets = [
+            Self::create_target(device, format, &layout, &sampler, &params, width, height),
+            Self::create_target(device, format, &layout, &sampler, &params, width, height),
+        ];
+        PostProcess { format, layout, sampler, params, copy, pipelines, targets, width, height }
+    }
+
+    fn create_target(device: &wgpu::Device, format: wgpu::TextureFormat, layout: &wgpu::BindGroupLayout, sampler: &wgpu::Sampler, params: &wgpu::Buffer, width: u32, height: u32) -> Target {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("post target"),
+            size: wgpu::Extent3d { width, height, depth_or_array_layers: 1 },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("post target"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(sampler) },
+                wgpu::BindGroupEntry { binding: 2, resource: params.as_entire_binding() },
+            ],
+        });
+        Target { view, bind_group }
+    }
+
+    // the window changed size, the offscreen textures have to match it
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let (width, height) = (width.max(1), height.max(1));
+        if (width, height) == (self.width, self.height) {
+            return;
+        }
+        self.width = width;
+        self.height = height;
+        for target in self.targets.iter_mut() {
+            *target = Self::create_target(device, self.format, &self.layout, &self.sampler, &self.params, width, height);
+        }
+    }
+
+    // draw the game into this instead of the window
+    pub fn scene_view(&self) -> &wgpu::TextureView {
+        &self.targets[0].view
+    }
+
+    // draw the scene to output through each effect in order. flash is how white the screen is, 0 to 1
+    pub fn run(&self, encoder: &mut wgpu::CommandEncoder, queue: &wgpu::Queue, output: &wgpu::TextureView, effects: &[Effect], flash: f32) {
+        let params = PostParams { resolution: [self.width as f32, self.height as f32], flash, _padding: 0.0 };
+        queue.write_buffer(&self.params, 0, bytemuck::bytes_of(&params));
+        // with nothing turned on the scene still has to get to the window
+        let mut passes: Vec<&wgpu::RenderPipeline> = effects.iter()
+            .filter_map(|effect| self.pipelines.iter().find(|(e, _)| e == effect).map(|(_, p)| p))
+            .collect();
+        if passes.is_empty() {
+            passes.push(&self.copy);
+        }
+        let last = passes.len() - 1;
+        for (i, pipeline) in passes.into_iter().enumerate() {
+            // read from one texture, write to the other one, and to the window on the last pass
+            let source = &self.targets[i % 2];
+            let dest = if i == last { output } else { &self.targets[(i + 1) % 2].view };
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("post"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: dest,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            rpass.set_pipeline(pipeline);
+            rpass.set_bind_group(0, &source.bind_group, &[]);
+            rpass.draw(0..3, 0..1);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+// Full-screen effects that run after the game has been drawn to an offscreen texture.
+// Every effect reads the picture so far from t_source and writes the next one.
+
+struct Params {
+    // size of the window in pixels
+    resolution: vec2<f32>,
+    // how white the screen is flashed, 0 to 1
+    flash: f32,
+    _padding: f32,
+}
+
+@group(0) @binding(0)
+var t_source: texture_2d<f32>;
+@group(0) @binding(1)
+var s_source: sampler;
+@group(0) @binding(2)
+var<uniform> params: Params;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // 0,0 is the top left of the screen, like texture coordinates
+    @location(0) uv: vec2<f32>,
+}
+
+// One big triangle that covers the whole screen, so no vertex buffer is needed.
+// Vertices 0, 1, 2 go to uv (0,0), (2,0), (0,2), everything past 1 is off screen and gets clipped.
+@vertex
+fn vs_main(@builtin(vertex_index) in_vertex_index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((in_vertex_index << 1u) & 2u), f32(in_vertex_index & 2u));
+    return VertexOutput(
+        vec4(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0),
+        uv
+    );
+}
+
+fn source(uv: vec2<f32>) -> vec4<f32> {
+    // sampleLevel so it can be used in loops
+    return textureSampleLevel(t_source, s_source, uv, 0.0);
+}
+
+// Just copy the picture, for when every effect is off
+@fragment
+fn fs_copy(in: VertexOutput) -> @location(0) vec4<f32> {
+    return source(in.uv);
+}
+
+// Dark lines across every other row of pixels, like an old TV
+@fragment
+fn fs_scanlines(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = source(in.uv);
+    let row = floor(in.clip_position.y);
+    let scan = select(1.0, 0.65, row % 2.0 == 1.0);
+    return vec4(color.rgb * scan, color.a);
+}
+
+// Darker towards the corners
+@fragment
+fn fs_vignette(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = source(in.uv);
+    let d = distance(in.uv, vec2(0.5, 0.5));
+    return vec4(color.rgb * smoothstep(0.85, 0.35, d), color.a);
+}
+
+// only the bright parts of a color glow
+fn bright(color: vec3<f32>) -> vec3<f32> {
+    return max(color - vec3(0.6), vec3(0.0)) * 2.5;
+}
+
+// Bright things (bullets, the white parts of the blocks) bleed light onto what's around them.
+// Samples two rings around each pixel, it's cheap and good enough for small sprites.
+@fragment
+fn fs_bloom(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = source(in.uv);
+    let texel = 1.0 / params.resolution;
+    var glow = vec3(0.0);
+    for (var i = 0; i < 12; i++) {
+        let angle = f32(i) * 6.2831853 / 12.0;
+        let dir = vec2(cos(angle), sin(angle)) * texel;
+        glow += bright(source(in.uv + dir * 4.0).rgb) * 0.6;
+        glow += bright(source(in.uv + dir * 9.0).rgb) * 0.4;
+    }
+    return vec4(color.rgb + glow / 12.0, color.a);
+}
+
+// Fade the whole screen towards white
+@fragment
+fn fs_flash(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = source(in.uv);
+    return vec4(mix(color.rgb, vec3(1.0), params.flash), color.a);
+}
@@ -0,0 +1,218 @@
+use image::{Rgba, RgbaImage};
+
+use crate::assets;
+use crate::atlas::SpriteAtlas;
+use crate::bitmap_font::BitmapFont;
+use crate::block_game::{BlockPlayScene, Difficulty};
+use crate::camera::GPUCamera;
+use crate::simulation::Simulation;
+use crate::space_game::SpaceScene;
+use crate::sprite::{self, GPUSprite};
+use crate::{WINDOW_WIDTH, WINDOW_HEIGHT};
+
+// CPU version of shader.wgsl, for checking what a frame looks like without a GPU.
+// Same rules as the shader:
+//  - world coordinates have 0,0 at the bottom left, the camera picks which part of the world is on screen
+//  - sprites are drawn in depth order (see sprite::sort_by_depth), later sprites on top
+//  - sprites are rotated around their pivot
+//  - UVs are flipped in Y (the top of a sprite samples the top of its from_region)
+//  - texels are multiplied by the sprite's color, then alpha blended over what was there
+//    (fully see-through ones are skipped, like the shader's discard)
+//  - the sheet and the frame are sRGB, so like the GPU the tint and blend happen on linear
+//    colors and the result is turned back into sRGB
+//  - the default wgpu sampler: nearest texel, clamped to the edge
+// The image is cleared to black first, just like the render pass.
+pub fn rasterize(camera: &GPUCamera, sprites: &[GPUSprite], atlas: &RgbaImage, width: u32, height: u32) -> RgbaImage {
+    let mut out = RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255]));
+    draw_sprites(&mut out, camera, sprites, atlas);
+    out
+}
+
+// rasterize without clearing first, for drawing more sprites (ex. bitmap font text) on top of a frame
+pub fn draw_sprites(out: &mut RgbaImage, camera: &GPUCamera, sprites: &[GPUSprite], atlas: &RgbaImage) {
+    let (width, height) = out.dimensions();
+    let (atlas_w, atlas_h) = atlas.dimensions();
+    // how many world units one pixel covers
+    let unit_x = camera.screen_size[0] / width as f32;
+    let unit_y = camera.screen_size[1] / height as f32;
+    let mut sprites = sprites.to_vec();
+    sprite::sort_by_depth(&mut sprites);
+    for sprite in &sprites {
+        let [x, y, w, h] = sprite.to_region;
+        // zero sized sprites are the hidden ones
+        if w <= 0.0 || h <= 0.0 {
+            continue;
+        }
+        let pivot_x = x + sprite.pivot[0] * w;
+        let pivot_y = y + sprite.pivot[1] * h;
+        let (sin, cos) = sprite.rotation.sin_cos();
+        // world space box around the rotated corners
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+        for [cx, cy] in [[x, y], [x + w, y], [x, y + h], [x + w, y + h]] {
+            let (dx, dy) = (cx - pivot_x, cy - pivot_y);
+            let rx = pivot_x + dx * cos - dy * sin;
+            let ry = pivot_y + dx * sin + dy * cos;
+            min_x = min_x.min(rx);
+            max_x = max_x.max(rx);
+            min_y = min_y.min(ry);
+            max_y = max_y.max(ry);
+        }
+        // only look at the pixels the sprite could cover
+        let px_min = (((min_x - camera.screen_pos[0]) / unit_x).floor().max(0.0)) as u32;
+        let px_max = (((max_x - camera.screen_pos[0]) / unit_x).ceil().min(width as f32)).max(0.0) as u32;
+        let py_min = (((camera.screen_pos[1] + camera.screen_size[1] - max_y) / unit_y).floor().max(0.0)) as u32;
+        let py_max = (((camera.screen_pos[1] + camera.screen_size[1] - min_y) / unit_y).ceil().min(height as f32)).max(0.0) as u32;
+        for py in py_min..py_max {
+            for px in px_min..px_max {
+                // world position of the pixel center, image rows go top to bottom
+                let wx = camera.screen_pos[0] + (px as f32 + 0.5) * unit_x;
+                let wy = camera.screen_pos[1] + camera.screen_size[1] - (py as f32 + 0.5) * unit_y;
+                // turn the pixel back the other way around the pivot to find where it is on the unrotated sprite
+                let (dx, dy) = (wx - pivot_x, wy - pivot_y);
+                let lx = pivot_x + dx * cos + dy * sin;
+                let ly = pivot_y - dx * sin + dy * cos;
+                if lx < x || lx >= x + w || ly < y || ly >= y + h {
+                    continue;
+                }
+                // where in the sprite we are, then where that is on the sprite sheet
+                let fx = (lx - x) / w;
+                let fy = (ly - y) / h;
+                let u = sprite.from_region[0] + fx * sprite.from_region[2];
+                let v = sprite.from_region[1] + (1.0 - fy) * sprite.from_region[3];
+                let tx = ((u * atlas_w as f32).floor() as i64).clamp(0, atlas_w as i64 - 1) as u32;
+                let ty = ((v * atlas_h as f32).floor() as i64).clamp(0, atlas_h as i64 - 1) as u32;
+                let texel = atlas.get_pixel(tx, ty);
+                let alpha = texel[3] as f32 / 255.0 * sprite.color[3];
+                if alpha < 0.01 {
+                    continue;
+                }
+                // wgpu::BlendState::ALPHA_BLENDING, the frame stays opaque since it starts out opaque
+                let dst = out.get_pixel_mut(px, py);
+                for c in 0..3 {
+                    let color = srgb_to_linear(texel[c]) * sprite.color[c];
+                    let blended = color * alpha + srgb_to_linear(dst[c]) * (1.0 - alpha);
+                    dst[c] = linear_to_srgb(blended);
+                }
+            }
+        }
+    }
+}
+
+// what sampling an Rgba8UnormSrgb texture gives the shader
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// what writing to an sRGB render target stores
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (c * 255.0).round() as u8
+}
+
+// how many pixels differ by more than tolerance in any channel, None if the sizes don't match.
+// For comparing a rasterized frame against a golden PNG.
+#[cfg(test)]
+fn count_different_pixels(a: &RgbaImage, b: &RgbaImage, tolerance: u8) -> Option<usize> {
+    if a.dimensions() != b.dimensions() {
+        return None;
+    }
+    Some(a.pixels().zip(b.pixels()).filter(|(pa, pb)| {
+        pa.0.iter().zip(pb.0.iter()).any(|(ca, cb)| ca.abs_diff(*cb) > tolerance)
+    }).count())
+}
+
+// Headless snapshot: run a screen for a second with no input and save what it looks like.
+// screen is one of "title", "block" or "space". The hud is drawn with the bitmap font.
+pub fn write_snapshot(screen: &str, out_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    snapshot(screen)?.save(out_path)?;
+    Ok(())
+}
+
+// what write_snapshot saves
+pub fn snapshot(screen: &str) -> Result<RgbaImage, Box<dyn std::error::Error>> {
+    let root = assets::find_root();
+    let sprite_atlas = SpriteAtlas::load(root.join(assets::SPRITE_ATLAS))?;
+    let atlas = image::open(&sprite_atlas.image_path)?.to_rgba8();
+    let mut font = BitmapFont::load(root.join(assets::FONT))?;
+    // same seed every time so snapshots can be compared
+    let mut sim = Simulation::new(1.0 / 60.0, sprite_atlas, 0, &mut font);
+    match screen {
+        "block" => sim.go_to(Box::new(BlockPlayScene { difficulty: Difficulty::Easy }), &mut font),
+        "space" => sim.go_to(Box::new(SpaceScene), &mut font),
+        _ => {}
+    }
+    let input = crate::input::Input::default();
+    for _ in 0..60 {
+        sim.step(&input, &mut font);
+    }
+    // with the backdrop behind everything, the same as main draws it
+    let mut sprites = sim.interpolated_sprites(1.0);
+    sprites.push(sprite::background(&sim.camera, sim.atlas.region("background")?));
+    let mut frame = rasterize(&sim.camera, &sprites, &atlas, WINDOW_WIDTH as u32, WINDOW_HEIGHT as u32);
+    // text is laid out in screen space, so it's drawn with a camera at the origin
+    let font_img = image::open(&font.image_path)?.to_rgba8();
+    draw_sprites(&mut frame, &GPUCamera::default(), &font.layout_hud(&sim.hud(), sim.text_offset), &font_img);
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // one white texel stretched over the whole frame
+    fn fill(color: [f32;4]) -> Rgba<u8> {
+        let sprite = GPUSprite { to_region: [0.0, 0.0, WINDOW_WIDTH, WINDOW_HEIGHT], from_region: [0.0, 0.0, 1.0, 1.0], color, ..GPUSprite::hidden() };
+        let atlas = RgbaImage::from_pixel(1, 1, Rgba([255, 255, 255, 255]));
+        *rasterize(&GPUCamera::default(), &[sprite], &atlas, 4, 3).get_pixel(1, 1)
+    }
+
+    #[test]
+    fn tints_and_blends_in_linear_space() {
+        // half of linear white is 188 in sRGB, not 128
+        assert_eq!(fill([1.0, 1.0, 1.0, 0.5]), Rgba([188, 188, 188, 255]));
+        assert_eq!(fill([1.0, 0.5, 0.0, 1.0]), Rgba([255, 188, 0, 255]));
+    }
+
+    // Each screen against its golden in tests/golden. When a change to how things look is on purpose,
+    // make new ones with: hello-triangle --snapshot <screen> tests/golden/<screen>.png
+    fn matches_golden(screen: &str) {
+        let golden = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.png", screen));
+        let golden = image::open(golden).unwrap().to_rgba8();
+        let frame = snapshot(screen).unwrap();
+        // a few pixels of leeway for float differences between machines
+        let different = count_different_pixels(&frame, &golden, 2).expect("snapshot and golden aren't the same size");
+        assert!(different <= 16, "{} pixels of the {} screen changed", different, screen);
+    }
+
+    #[test]
+    fn title_matches_golden() {
+        matches_golden("title");
+    }
+
+    #[test]
+    fn block_game_matches_golden() {
+        matches_golden("block");
+    }
+
+    #[test]
+    fn space_game_matches_golden() {
+        matches_golden("space");
+    }
+
+    #[test]
+    fn srgb_round_trips() {
+        for c in 0..=255 {
+            assert_eq!(linear_to_srgb(srgb_to_linear(c)), c);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::ActionMap;
+use crate::high_scores::HighScores;
+use crate::input::{Input, InputEvent};
+use crate::input_source::InputSource;
+use crate::json_file::{self, JsonError};
+
+// A run of the game: everything the simulation depends on besides its own code.
+// Given the same seed, tick rate, controls, high scores and input on the same updates, the game plays out the same way
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    // updates per second, dt changes how things move so it has to match
+    pub tick_rate: f64,
+    // the controls decide what the keys do
+    pub actions: ActionMap,
+    // the high scores decide whether a game over asks for initials
+    #[serde(default)]
+    pub high_scores: HighScores,
+    // how many updates were recorded
+    pub ticks: u64,
+    // input events and the update they happened before, only for updates where something happened
+    frames: Vec<(u64, Vec<InputEvent>)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, tick_rate: f64, actions: ActionMap, high_scores: HighScores) -> Replay {
+        Replay { seed, tick_rate, actions, high_scores, ticks: 0, frames: vec![] }
+    }
+
+    // call once per update, before it runs, with what input saw since the last one
+    pub fn record(&mut self, input: &Input) {
+        if !input.events().is_empty() {
+            self.frames.push((self.ticks, input.events().to_vec()));
+        }
+        self.ticks += 1;
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay, JsonError> {
+        json_file::load(path)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), JsonError> {
+        json_file::save_compact(path, self)
+    }
+}
+
+// Feeds a replay's input back in, one update at a time, in place of the keyboard, mouse and gamepads
+pub struct ReplayPlayer {
+    replay: Replay,
+    tick: u64,
+    // index of the next frame in replay.frames
+    next: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> ReplayPlayer {
+        ReplayPlayer { replay, tick: 0, next: 0 }
+    }
+}
+
+impl InputSource for ReplayPlayer {
+    fn poll(&mut self, input: &mut Input) {
+        if self.finished() {
+            return;
+        }
+        if let Some((tick, events)) = self.replay.frames.get(self.next) {
+            if *tick == self.tick {
+                for &event in events {
+                    input.apply(event);
+                }
+                self.next += 1;
+            }
+        }
+        self.tick += 1;
+    }
+
+    // the whole replay has been played
+    fn finished(&self) -> bool {
+        self.tick >= self.replay.ticks
+    }
+}
@@ -0,0 +1,113 @@
+use rand::rngs::StdRng;
+
+use crate::actions::ActionMap;
+use crate::animation::Animators;
+use crate::atlas::SpriteAtlas;
+use crate::camera::GPUCamera;
+use crate::game_state::GameState;
+use crate::high_scores::HighScores;
+use crate::hud::{Hud, MeasureText};
+use crate::input::Input;
+use crate::particles::ParticleSystem;
+use crate::sprite::GPUSprite;
+use crate::tween::Tweens;
+
+// Everything a screen is allowed to touch while it runs
+pub struct SceneContext<'a> {
+    pub gs: &'a mut GameState,
+    pub sprites: &'a mut Vec<GPUSprite>,
+    // which part of the world is on screen, every screen starts at the origin
+    pub camera: &'a mut GPUCamera,
+    // effects that live on their own once started, cleared between screens
+    pub particles: &'a mut ParticleSystem,
+    // animations of sprites, the camera or the text, also cleared between screens
+    pub tweens: &'a mut Tweens,
+    // sprites playing animations from the atlas, cleared between screens
+    pub animators: &'a mut Animators,
+    // how far the text is moved from where it normally goes
+    pub text_offset: &'a mut [f32;2],
+    // how white the screen is flashed, 0 to 1 (drawn by the flash effect in post.rs)
+    pub flash: &'a mut f32,
+    // which keys do what
+    pub actions: &'a mut ActionMap,
+    // best scores of each game, main saves them when they change
+    pub high_scores: &'a mut HighScores,
+    pub rng: &'a mut StdRng,
+    // names for the regions of the sprite sheet
+    pub atlas: &'a SpriteAtlas,
+    // the font the hud is drawn in, so clicking on text goes by how big it really is
+    pub font: &'a mut dyn MeasureText,
+    // seconds of game time in this update
+    pub dt: f32,
+}
+
+// What a screen wants to happen after its update
+pub enum Transition {
+    // keep running this screen
+    None,
+    // leave this screen and enter the given one
+    To(Box<dyn Scene>),
+}
+
+// One screen of the game (title, block game, game over...)
+pub trait Scene {
+    // called once when the screen becomes active
+    fn enter(&mut self, _ctx: &mut SceneContext) {}
+    // called every update, returns which screen should run next
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition;
+    // called once right before the next screen is entered
+    fn exit(&mut self, _ctx: &mut SceneContext) {}
+    // put the text shown on top of the sprites into the hud
+    fn draw(&self, gs: &GameState, hud: &mut Hud);
+    // whether esc goes back to the title from here
+    fn escapable(&self) -> bool {
+        true
+    }
+    // whether the screen is waiting for typed text, only then do characters get sent
+    fn wants_text(&self) -> bool {
+        false
+    }
+}
+
+// Owns the active screen and does the enter/exit bookkeeping for transitions
+pub struct SceneManager {
+    current: Box<dyn Scene>,
+}
+
+impl SceneManager {
+    pub fn new(mut first: Box<dyn Scene>, ctx: &mut SceneContext) -> SceneManager {
+        first.enter(ctx);
+        SceneManager { current: first }
+    }
+
+    pub fn update(&mut self, ctx: &mut SceneContext, input: &Input) {
+        if let Transition::To(next) = self.current.update(ctx, input) {
+            self.go_to(next, ctx);
+        }
+    }
+
+    // switch screens from outside of a scene (ex. esc goes back to the title anywhere)
+    pub fn go_to(&mut self, mut next: Box<dyn Scene>, ctx: &mut SceneContext) {
+        self.current.exit(ctx);
+        *ctx.camera = GPUCamera::default();
+        ctx.particles.clear();
+        ctx.tweens.clear();
+        ctx.animators.clear();
+        *ctx.text_offset = [0.0, 0.0];
+        *ctx.flash = 0.0;
+        next.enter(ctx);
+        self.current = next;
+    }
+
+    pub fn draw(&self, gs: &GameState, hud: &mut Hud) {
+        self.current.draw(gs, hud);
+    }
+
+    pub fn escapable(&self) -> bool {
+        self.current.escapable()
+    }
+
+    pub fn wants_text(&self) -> bool {
+        self.current.wants_text()
+    }
+}
@@ -0,0 +1,54 @@
+use crate::camera::ScalePolicy;
+use crate::post::Effect;
+
+// Options the player can change while the game is running
+pub struct Settings {
+    // how the game fits into the window (F1 cycles through them)
+    pub scale_policy: ScalePolicy,
+    // draw text with the pixel font instead of the system font (F2 switches)
+    pub bitmap_font: bool,
+    // full-screen effects, F3 to F6 turn them on and off
+    pub crt_scanlines: bool,
+    pub vignette: bool,
+    pub bloom: bool,
+    // white flash when a game ends
+    pub flash: bool,
+    // the ship in the space game follows the mouse or a finger instead of the arrow keys (F7 switches)
+    pub mouse_aim: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            scale_policy: ScalePolicy::Letterbox,
+            bitmap_font: false,
+            crt_scanlines: false,
+            vignette: false,
+            bloom: false,
+            flash: true,
+            mouse_aim: false,
+        }
+    }
+}
+
+impl Settings {
+    // the effects that are on, in the order they run. flash is how white the screen is right now,
+    // the flash pass is skipped while there's nothing to flash
+    pub fn effects(&self, flash: f32) -> Vec<Effect> {
+        let mut effects = vec![];
+        if self.bloom {
+            effects.push(Effect::Bloom);
+        }
+        if self.flash && flash > 0.0 {
+            effects.push(Effect::Flash);
+        }
+        if self.vignette {
+            effects.push(Effect::Vignette);
+        }
+        // last so the lines aren't blurred or brightened by anything
+        if self.crt_scanlines {
+            effects.push(Effect::Scanlines);
+        }
+        effects
+    }
+}
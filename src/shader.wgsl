@@ -20,7 +20,15 @@ struct Camera {
 // GPUSprite, from before
 struct GPUSprite {
     to_rect:vec4<f32>,
-    from_rect:vec4<f32>
+    from_rect:vec4<f32>,
+    // tint multiplied with the texture, alpha fades the whole sprite
+    color:vec4<f32>,
+    // counterclockwise, in radians
+    rotation:f32,
+    // only used for sorting on the CPU
+    depth:f32,
+    // point to rotate around, as a fraction of the size
+    pivot:vec2<f32>
 }
 
 // One binding for the camera...
@@ -34,6 +42,7 @@ var<storage, read> sprites: array<GPUSprite>;
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) tex_coords: vec2<f32>,
+    @location(1) color: vec4<f32>,
 }
 
 @vertex
@@ -42,8 +51,10 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32,
            @builtin(instance_index) sprite_index:u32) -> VertexOutput {
     // The corner and size of the sprite in world space.
     // Which sprite? sprites[sprite_index]
-    let corner:vec4<f32> = vec4(sprites[sprite_index].to_rect.xy,0.,1.);
+    let corner:vec2<f32> = sprites[sprite_index].to_rect.xy;
     let size:vec2<f32> = sprites[sprite_index].to_rect.zw;
+    // The pivot in world space, the sprite turns around this point
+    let pivot:vec2<f32> = corner + sprites[sprite_index].pivot*size;
     // The corner and size of the texture area in UVs
     let tex_corner:vec2<f32> = sprites[sprite_index].from_rect.xy;
     let tex_size:vec2<f32> = sprites[sprite_index].from_rect.zw;
@@ -51,11 +62,18 @@ fn vs_main(@builtin(vertex_index) in_vertex_index: u32,
     let which_vtx:vec2<f32> = VERTICES[in_vertex_index];
     // Which corner of the UV square we need to draw (UV coordinates are flipped in Y)
     let which_uv: vec2<f32> = vec2(VERTICES[in_vertex_index].x, 1.0 - VERTICES[in_vertex_index].y);
+    // Offset corner by size * which_vtx to get the right corner, measured from the pivot, and rotate that around the pivot
+    let from_pivot:vec2<f32> = corner + which_vtx*size - pivot;
+    let c:f32 = cos(sprites[sprite_index].rotation);
+    let s:f32 = sin(sprites[sprite_index].rotation);
+    let world:vec2<f32> = pivot + vec2(from_pivot.x*c - from_pivot.y*s, from_pivot.x*s + from_pivot.y*c);
     return VertexOutput(
-        // Offset corner by size * which_vtx to get the right corner, then do camera stuff. Dividing screen size by 2 and the last subtraction are to deal with the NDC coordinate space, which goes from -1 to 1 in WGPU.
-        ((corner + vec4(which_vtx*size,0.,0.) - vec4(camera.screen_pos,0.,0.)) / vec4(camera.screen_size/2., 1.0, 1.0)) - vec4(1.0, 1.0, 0.0, 0.0),
+        // Then do camera stuff. Dividing screen size by 2 and the last subtraction are to deal with the NDC coordinate space, which goes from -1 to 1 in WGPU.
+        (vec4(world - camera.screen_pos, 0., 1.) / vec4(camera.screen_size/2., 1.0, 1.0)) - vec4(1.0, 1.0, 0.0, 0.0),
         // Offset texture corner by tex_size * which_uv to get the right corner
-        tex_corner + which_uv*tex_size
+        tex_corner + which_uv*tex_size,
+        // Every corner gets the same tint
+        sprites[sprite_index].color
     );
 }
 
@@ -72,9 +90,10 @@ var s_diffuse: sampler;
 @fragment
 fn fs_main(in:VertexOutput) -> @location(0) vec4<f32> {
     // And we use the tex coords from the vertex output to sample from the texture.
-    let color:vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords);
-    // This is new: if the alpha value of the color is very low, don't draw any fragment here.
-    // This is like "cutout" transparency.
-    if color.w < 0.2 { discard; }
+    // Then tint it with the sprite's color.
+    let color:vec4<f32> = textureSample(t_diffuse, s_diffuse, in.tex_coords) * in.color;
+    // Fully see-through fragments don't need to be drawn, everything else gets alpha blended
+    // with what's already there by the pipeline's blend state.
+    if color.w < 0.01 { discard; }
     return color;
 }
\ No newline at end of file
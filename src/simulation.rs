@@ -0,0 +1,316 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::actions::{Action, ActionMap};
+use crate::animation::Animators;
+use crate::atlas::SpriteAtlas;
+use crate::camera::GPUCamera;
+use crate::game_state::{self, GameState};
+use crate::high_scores::HighScores;
+use crate::hud::{Hud, MeasureText};
+use crate::input::Input;
+use crate::particles::ParticleSystem;
+use crate::scene::{Scene, SceneContext, SceneManager};
+use crate::sprite::GPUSprite;
+use crate::title::TitleScene;
+use crate::tween::{Tweens, TweenTargets};
+use crate::SPRITE_SIZE;
+
+// The whole game without any window, GPU or text rendering attached.
+// main.rs feeds it input and draws whatever ends up in `sprites` and `text()`,
+// but it can just as well be stepped by hand (ex. from a test with no GPU).
+pub struct Simulation {
+    // state of game at any time
+    pub gs: GameState,
+    // sprites the active screen wants drawn
+    pub sprites: Vec<GPUSprite>,
+    // which part of the world the sprites are seen through
+    pub camera: GPUCamera,
+    // effects drawn on top of the screen's sprites
+    pub particles: ParticleSystem,
+    // values being animated over time
+    pub tweens: Tweens,
+    // sprites flipping through frames of the sprite sheet
+    pub animators: Animators,
+    // how far the text is moved from where it normally goes (for sliding it around)
+    pub text_offset: [f32;2],
+    // how white the screen is flashed, 0 to 1
+    pub flash: f32,
+    // which keys do what, main loads them from controls.json
+    pub actions: ActionMap,
+    // best scores, main loads and saves them with high_scores.json
+    pub high_scores: HighScores,
+    // all the game's randomness comes from here, the same seed plays out the same way
+    pub rng: StdRng,
+    // regions of the sprite sheet the sprites use
+    pub atlas: SpriteAtlas,
+    // seconds of game time in one step
+    pub dt: f32,
+    // sprites as they were before the last step, to interpolate between
+    prev_sprites: Vec<GPUSprite>,
+    prev_camera: GPUCamera,
+    prev_text_offset: [f32;2],
+    scenes: SceneManager,
+}
+
+impl Simulation {
+    // starts on the title screen, dt is how many seconds one step lasts.
+    // seed decides everything random, so a run can be played again.
+    // font is whatever the hud is being drawn in, screens measure their text with it
+    pub fn new(dt: f32, atlas: SpriteAtlas, seed: u64, font: &mut dyn MeasureText) -> Simulation {
+        let mut gs = game_state::init_game_state();
+        let mut sprites = vec![];
+        let mut camera = GPUCamera::default();
+        let mut particles = ParticleSystem::default();
+        let mut tweens = Tweens::default();
+        let mut animators = Animators::default();
+        let mut text_offset = [0.0, 0.0];
+        let mut flash = 0.0;
+        let mut actions = ActionMap::default();
+        let mut high_scores = HighScores::default();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let scenes = SceneManager::new(
+            Box::new(TitleScene::new()),
+            &mut SceneContext { gs: &mut gs, sprites: &mut sprites, camera: &mut camera, particles: &mut particles, tweens: &mut tweens, animators: &mut animators, text_offset: &mut text_offset, flash: &mut flash, actions: &mut actions, high_scores: &mut high_scores, rng: &mut rng, atlas: &atlas, font, dt },
+        );
+        let prev_sprites = sprites.clone();
+        Simulation { gs, sprites, camera, particles, tweens, animators, text_offset, flash, actions, high_scores, rng, atlas, dt, prev_sprites, prev_camera: camera, prev_text_offset: text_offset, scenes }
+    }
+
+    // advance the game by one fixed update using this tick's input
+    pub fn step(&mut self, input: &Input, font: &mut dyn MeasureText) {
+        self.prev_sprites.clone_from(&self.sprites);
+        self.prev_camera = self.camera;
+        self.prev_text_offset = self.text_offset;
+        // Reset to title screen when esc is pressed anywhere (that lets it)
+        if self.actions.is_down(input, Action::Back) && self.scenes.escapable() {
+            self.go_to(Box::new(TitleScene::new()), font);
+        }
+        // run whichever screen is active, it decides which screen comes next
+        let mut ctx = SceneContext { gs: &mut self.gs, sprites: &mut self.sprites, camera: &mut self.camera, particles: &mut self.particles, tweens: &mut self.tweens, animators: &mut self.animators, text_offset: &mut self.text_offset, flash: &mut self.flash, actions: &mut self.actions, high_scores: &mut self.high_scores, rng: &mut self.rng, atlas: &self.atlas, font, dt: self.dt };
+        self.scenes.update(&mut ctx, input);
+        let mut targets = TweenTargets { sprites: &mut self.sprites, camera: &mut self.camera, text_offset: &mut self.text_offset, flash: &mut self.flash };
+        self.tweens.update(self.dt, &mut targets, &mut self.gs);
+        self.animators.update(self.dt, &mut self.sprites);
+        self.particles.update(self.dt, &mut self.rng);
+    }
+
+    // jump straight to a screen, running its enter/exit like a normal transition
+    pub fn go_to(&mut self, scene: Box<dyn Scene>, font: &mut dyn MeasureText) {
+        let mut ctx = SceneContext { gs: &mut self.gs, sprites: &mut self.sprites, camera: &mut self.camera, particles: &mut self.particles, tweens: &mut self.tweens, animators: &mut self.animators, text_offset: &mut self.text_offset, flash: &mut self.flash, actions: &mut self.actions, high_scores: &mut self.high_scores, rng: &mut self.rng, atlas: &self.atlas, font, dt: self.dt };
+        self.scenes.go_to(scene, &mut ctx);
+    }
+
+    // sprites blended between the last two steps, alpha is 0 at the previous step and 1 at the current one.
+    // particles go on the end as they are now
+    pub fn interpolated_sprites(&self, alpha: f32) -> Vec<GPUSprite> {
+        let mut sprites = self.blend_sprites(alpha);
+        self.particles.append_sprites(&mut sprites);
+        sprites
+    }
+
+    fn blend_sprites(&self, alpha: f32) -> Vec<GPUSprite> {
+        // the screen swapped its sprites out, nothing to blend with
+        if self.prev_sprites.len() != self.sprites.len() {
+            return self.sprites.clone();
+        }
+        self.sprites.iter().zip(&self.prev_sprites).map(|(now, prev)| {
+            let mut sprite = *now;
+            let dx = now.to_region[0] - prev.to_region[0];
+            let dy = now.to_region[1] - prev.to_region[1];
+            // only blend sprites that moved a little, anything that changed size or
+            // jumped across the screen (a bullet going back to the ship) just snaps
+            let same_size = now.to_region[2] == prev.to_region[2] && now.to_region[3] == prev.to_region[3];
+            if same_size && dx.abs() < SPRITE_SIZE*2.0 && dy.abs() < SPRITE_SIZE*2.0 {
+                sprite.to_region[0] = prev.to_region[0] + dx*alpha;
+                sprite.to_region[1] = prev.to_region[1] + dy*alpha;
+                sprite.rotation = prev.rotation + (now.rotation - prev.rotation)*alpha;
+                // fades and flashes too
+                for c in 0..4 {
+                    sprite.color[c] = prev.color[c] + (now.color[c] - prev.color[c])*alpha;
+                }
+            }
+            sprite
+        }).collect()
+    }
+
+    // camera blended between the last two steps the same way as the sprites
+    pub fn interpolated_camera(&self, alpha: f32) -> GPUCamera {
+        let mut camera = self.camera;
+        for i in 0..2 {
+            camera.screen_pos[i] = self.prev_camera.screen_pos[i] + (self.camera.screen_pos[i] - self.prev_camera.screen_pos[i])*alpha;
+        }
+        camera
+    }
+
+    // text offset blended the same way
+    pub fn interpolated_text_offset(&self, alpha: f32) -> [f32;2] {
+        std::array::from_fn(|i| self.prev_text_offset[i] + (self.text_offset[i] - self.prev_text_offset[i])*alpha)
+    }
+
+    // whether the active screen wants typed characters
+    pub fn wants_text(&self) -> bool {
+        self.scenes.wants_text()
+    }
+
+    // text the active screen wants shown on top of the sprites
+    pub fn hud(&self) -> Hud {
+        let mut hud = Hud::default();
+        self.scenes.draw(&self.gs, &mut hud);
+        hud
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    use winit::event::MouseButton;
+
+    use crate::assets;
+    use crate::bitmap_font::BitmapFont;
+    use crate::input::{InputEvent, Key};
+    use crate::input_source::{InputSource, ScriptedInput};
+    use crate::WINDOW_HEIGHT;
+
+    // the game and the pixel font its text is measured in
+    fn simulation() -> (Simulation, BitmapFont) {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let atlas = SpriteAtlas::load(root.join(assets::SPRITE_ATLAS)).unwrap();
+        let mut font = BitmapFont::load(root.join(assets::FONT)).unwrap();
+        (Simulation::new(1.0/60.0, atlas, 0, &mut font), font)
+    }
+
+    // step the game the way main does, with the script standing in for the keyboard
+    fn run(sim: &mut Simulation, font: &mut BitmapFont, input: &mut Input, script: &mut ScriptedInput, steps: usize) {
+        for _ in 0..steps {
+            script.poll(input);
+            sim.step(input, font);
+            input.next_frame();
+        }
+    }
+
+    // the text of a hud block, to tell which screen is up
+    fn hud_text(sim: &Simulation, name: &str) -> Option<String> {
+        sim.hud().get(name).map(|block| block.text.clone())
+    }
+
+    // move the mouse to the middle of a menu item and click it
+    fn click(sim: &Simulation, font: &mut BitmapFont, script: ScriptedInput, tick: u64, item: &str) -> ScriptedInput {
+        let [x, y, width, height] = sim.hud().get(item).unwrap().layout_bounds(font, sim.text_offset);
+        // the hud measures y down from the top, the mouse up from the bottom
+        let pos = InputEvent::MouseMove((x + width/2.0) as f64, (WINDOW_HEIGHT - (y + height/2.0)) as f64);
+        script.at(tick, pos).at(tick, InputEvent::Mouse(MouseButton::Left, true)).at(tick + 1, InputEvent::Mouse(MouseButton::Left, false))
+    }
+
+    #[test]
+    fn dropping_a_row_over_the_left_border_narrows_it() {
+        let (mut sim, mut font) = simulation();
+        let mut input = Input::default();
+        // a for the block game, 1 for easy, then space once the row is sliding
+        let mut script = ScriptedInput::default().tap(0, Key::A).tap(2, Key::Key1).tap(10, Key::Space);
+        run(&mut sim, &mut font, &mut input, &mut script, 10);
+        assert_eq!(sim.gs.drop_sprite_blocks, 5);
+        assert!(sim.gs.waiting);
+        // move the tower's left edge a block and a half in from the left of the row
+        let left = sim.sprites[sim.gs.row_start].to_region[0];
+        sim.gs.left_border = left + SPRITE_SIZE*1.5;
+        run(&mut sim, &mut font, &mut input, &mut script, 1);
+        assert!((sim.gs.row_width - SPRITE_SIZE*3.5).abs() < 0.01);
+        assert_eq!(sim.gs.drop_sprite_blocks, 4);
+        // the block that missed and the half that hung over are falling away
+        assert_eq!(sim.gs.fading.len(), 2);
+    }
+
+    #[test]
+    fn clicking_menus_picks_the_game_and_difficulty() {
+        let (mut sim, mut font) = simulation();
+        let mut input = Input::default();
+        // let the title finish dropping in before clicking it
+        let mut script = ScriptedInput::default();
+        run(&mut sim, &mut font, &mut input, &mut script, 60);
+        let mut script = click(&sim, &mut font, script, 60, "menu_0");
+        run(&mut sim, &mut font, &mut input, &mut script, 2);
+        assert!(hud_text(&sim, "heading").is_some());
+        // same for the difficulty menu sliding in
+        run(&mut sim, &mut font, &mut input, &mut script, 40);
+        let mut script = click(&sim, &mut font, script, 102, "menu_1");
+        run(&mut sim, &mut font, &mut input, &mut script, 3);
+        assert_eq!(hud_text(&sim, "level").as_deref(), Some("Level: 1"));
+        assert_eq!(sim.gs.speed, 6);
+    }
+
+    #[test]
+    fn clicking_next_to_a_menu_does_nothing() {
+        let (mut sim, mut font) = simulation();
+        let mut input = Input::default();
+        let mut script = ScriptedInput::default()
+            .at(60, InputEvent::MouseMove(10.0, (WINDOW_HEIGHT/2.0) as f64))
+            .at(60, InputEvent::Mouse(MouseButton::Left, true))
+            .at(61, InputEvent::Mouse(MouseButton::Left, false));
+        run(&mut sim, &mut font, &mut input, &mut script, 64);
+        assert_eq!(hud_text(&sim, "title").as_deref(), Some("Block Games!!!"));
+    }
+
+    #[test]
+    fn esc_leaves_a_game_for_the_title() {
+        let (mut sim, mut font) = simulation();
+        let mut input = Input::default();
+        let mut script = ScriptedInput::default().tap(0, Key::B);
+        run(&mut sim, &mut font, &mut input, &mut script, 5);
+        assert!(hud_text(&sim, "timer").is_some());
+        let mut script = script.tap(5, Key::Escape);
+        run(&mut sim, &mut font, &mut input, &mut script, 1);
+        assert_eq!(hud_text(&sim, "title").as_deref(), Some("Block Games!!!"));
+    }
+
+    #[test]
+    fn mouse_aim_fires_towards_the_pointer() {
+        let (mut sim, mut font) = simulation();
+        let mut input = Input::default();
+        // b for the space game with mouse aim on, the pointer up and well to the right of the ship
+        let pointer = [900.0, 400.0];
+        let mut script = ScriptedInput::default()
+            .tap(0, Key::B)
+            .at(0, InputEvent::MouseAim(true))
+            .at(0, InputEvent::MouseMove(pointer[0] as f64, pointer[1] as f64))
+            .tap(3, Key::Space);
+        run(&mut sim, &mut font, &mut input, &mut script, 4);
+        // fired from where the ship is after this step's move, then moved once along its direction
+        let ship = [sim.gs.cur_x, sim.gs.cur_y];
+        let [x, y] = sim.gs.bullets[0];
+        let [dx, dy] = [pointer[0] - (ship[0] + SPRITE_SIZE/2.0), pointer[1] - (ship[1] + SPRITE_SIZE/2.0)];
+        assert!(x > ship[0] + 1.0, "the shot went straight up from {:?} to {:?}", ship, [x, y]);
+        // towards the pointer from the middle of the ship, at the usual speed
+        let speed = sim.gs.bullet_speed;
+        assert!(((x - ship[0]) / speed - dx / dx.hypot(dy)).abs() < 1e-3);
+        assert!(((y - ship[1]) / speed - dy / dx.hypot(dy)).abs() < 1e-3);
+        // and it keeps going that way
+        run(&mut sim, &mut font, &mut input, &mut script, 1);
+        assert!(sim.gs.bullets[0][0] > x && sim.gs.bullets[0][1] > y);
+    }
+
+    #[test]
+    fn controls_menu_leaves_with_esc_or_a_rebound_back() {
+        let (mut sim, mut font) = simulation();
+        let mut input = Input::default();
+        let mut script = ScriptedInput::default().tap(0, Key::K).tap(2, Key::Escape);
+        run(&mut sim, &mut font, &mut input, &mut script, 2);
+        assert_eq!(hud_text(&sim, "title").as_deref(), Some("Controls"));
+        run(&mut sim, &mut font, &mut input, &mut script, 1);
+        assert_eq!(hud_text(&sim, "title").as_deref(), Some("Block Games!!!"));
+
+        // down to Back, bind it to q, then q leaves
+        let mut script = script.tap(4, Key::K);
+        for tick in (6..16).step_by(2) {
+            script = script.tap(tick, Key::Down);
+        }
+        let mut script = script.tap(16, Key::Return).tap(18, Key::Q).tap(20, Key::Q);
+        run(&mut sim, &mut font, &mut input, &mut script, 20 - 3);
+        assert_eq!(sim.actions.describe(Action::Back), "Q");
+        assert_eq!(hud_text(&sim, "title").as_deref(), Some("Controls"));
+        run(&mut sim, &mut font, &mut input, &mut script, 1);
+        assert_eq!(hud_text(&sim, "title").as_deref(), Some("Block Games!!!"));
+    }
+}
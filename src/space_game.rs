@@ -0,0 +1,243 @@
+use rand::Rng;
+
+use crate::actions::Action;
+use crate::clock::REFERENCE_TICK_RATE;
+use crate::game_state::{self, GameState};
+use crate::high_scores::{Game, ScoreEntry};
+use crate::hud::{Align, Anchor, FontFamily, Hud, TextBlock};
+use crate::input::Input;
+use crate::particles::Burst;
+use crate::scene::{Scene, SceneContext, Transition};
+use crate::sprite;
+use crate::tween::{Ease, Tween, TweenTarget};
+use crate::{WINDOW_WIDTH, WINDOW_HEIGHT, SPRITE_SIZE};
+
+// how many seconds a round of target practice lasts
+pub const SPACE_ROUND_SECONDS: f32 = 60.0;
+
+// sprite slots used by the space game
+const TARGET: usize = 0;
+const SHIP: usize = 1;
+const FIRST_BULLET: usize = 2;
+
+// how long the target flashes red after a hit
+const TARGET_FLASH_SECONDS: f32 = 0.25;
+// how far the ship leans into a turn (radians), and how quickly it gets there
+const SHIP_BANK: f32 = 0.3;
+const SHIP_BANK_SPEED: f32 = 10.0;
+// how quickly the ship catches up with the mouse in mouse aim (bigger is snappier)
+const AIM_FOLLOW: f32 = 12.0;
+
+// Space game - shoot the target sliding along the top of the screen
+pub struct SpaceScene;
+
+impl SpaceScene {
+    // put the next bullet at the ship, headed from the middle of the ship towards aim
+    // (where the mouse is, in mouse aim). Without one it goes straight up
+    fn fire(gs: &mut GameState, sprites: &mut [sprite::GPUSprite], aim: Option<[f32;2]>) {
+        let [dx, dy] = match aim {
+            Some([x, y]) => [x - (gs.cur_x + SPRITE_SIZE/2.0), y - (gs.cur_y + SPRITE_SIZE/2.0)],
+            None => [0.0, 1.0],
+        };
+        let length = (dx*dx + dy*dy).sqrt();
+        // the pointer right on the ship doesn't point anywhere
+        let direction = if length > 0.0 { [dx / length, dy / length] } else { [0.0, 1.0] };
+        gs.bullet_count += 1;
+        gs.bullet_moving = true;
+        gs.bullets[0] = [gs.cur_x, gs.cur_y];
+        gs.bullet_directions[0] = direction;
+        sprites[FIRST_BULLET].to_region = [gs.cur_x, gs.cur_y, SPRITE_SIZE, SPRITE_SIZE];
+        if gs.bullet_count == 2 || gs.bullet_count == 3{
+            let b = gs.bullet_count - 1;
+            gs.bullets[b] = [gs.cur_x, gs.cur_y];
+            gs.bullet_directions[b] = direction;
+            sprites[FIRST_BULLET + b].to_region = [gs.cur_x, gs.cur_y, SPRITE_SIZE, SPRITE_SIZE];
+        }
+    }
+}
+
+impl Scene for SpaceScene {
+    fn enter(&mut self, ctx: &mut SceneContext) {
+        *ctx.gs = game_state::init_game_state();
+        ctx.gs.start = true;
+        ctx.gs.timer = SPACE_ROUND_SECONDS;
+        *ctx.sprites = sprite::hidden_sprites(ctx.gs.drop_sprite_blocks*12);
+        // the target cycles through colors as it spins and the ship flickers, the frames are in the atlas
+        ctx.animators.play(TARGET, ctx.atlas.clip("target_colors"));
+        ctx.animators.play(SHIP, ctx.atlas.clip("ship_flicker"));
+    }
+
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition {
+        let gs = &mut *ctx.gs;
+        let sprites = &mut *ctx.sprites;
+        if !gs.start{
+            return Transition::None;
+        }
+        gs.timer -= ctx.dt;
+        if gs.timer <= 0.0{
+            return Transition::To(Box::new(SpaceGameOverScene::new()));
+        }
+        // speeds are per tick at the reference rate, scale them to this tick
+        let scale = ctx.dt * REFERENCE_TICK_RATE;
+
+        // target sprite
+        sprites[TARGET].to_region = [gs.target_x, gs.target_y, SPRITE_SIZE, SPRITE_SIZE];
+        // red right after a hit, back to normal as the flash wears off
+        gs.target_flash = (gs.target_flash - ctx.dt).max(0.0);
+        let flash = gs.target_flash / TARGET_FLASH_SECONDS;
+        sprites[TARGET].color = [1.0, 1.0 - 0.7*flash, 1.0 - 0.7*flash, 1.0];
+        let mut moven = gs.speed as f32 * scale;
+        if gs.direction{
+            moven = -moven;
+        }
+        if sprites[TARGET].to_region[1] == WINDOW_HEIGHT - SPRITE_SIZE{
+            gs.target_x = sprites[TARGET].to_region[0];
+            if gs.target_x >= 960.0 - moven{
+                gs.direction = true;
+            }else if gs.target_x < 0.0 + moven{
+                gs.direction = false;
+            }
+            gs.target_x += moven;
+            sprites[TARGET].to_region = [gs.target_x, WINDOW_HEIGHT - SPRITE_SIZE, SPRITE_SIZE, SPRITE_SIZE];
+        }
+
+        // ship sprite VVV
+        sprites[SHIP].to_region = [gs.cur_x, gs.cur_y, SPRITE_SIZE, SPRITE_SIZE];
+
+        // Bullet Sprites - initially invisible
+        let bullet_region = ctx.atlas.uv("bullet");
+        for b in 0..3 {
+            sprites[FIRST_BULLET + b].to_region = [gs.cur_x, gs.cur_y, 0.0, 0.0];
+            sprites[FIRST_BULLET + b].from_region = bullet_region;
+        }
+
+        // checks left and right movement, a stick moves the ship slower when it's only pushed a little.
+        // With mouse aim on the ship chases the pointer instead, easing in so it glides rather than jumps
+        let moved = if input.mouse_aim() {
+            let target = (input.mouse_pos().x as f32 - SPRITE_SIZE/2.0).clamp(0.0, WINDOW_WIDTH - SPRITE_SIZE);
+            (target - gs.cur_x) * (1.0 - (-AIM_FOLLOW * ctx.dt).exp())
+        } else {
+            6.0 * scale * ctx.actions.axis(input, Action::MoveLeft, Action::MoveRight)
+        };
+        let mut bank = 0.0;
+        if moved != 0.0 {
+            gs.cur_x += moved;
+            sprites[SHIP].to_region = [gs.cur_x, 0.0, SPRITE_SIZE, SPRITE_SIZE];
+            // lean as far as the keys would at their speed, never further
+            bank = -SHIP_BANK * (moved / (6.0 * scale)).clamp(-1.0, 1.0);
+        }
+        // lean into the turn, level out when not moving
+        let rotation = &mut sprites[SHIP].rotation;
+        *rotation += (bank - *rotation) * (1.0 - (-SHIP_BANK_SPEED * ctx.dt).exp());
+        if ctx.actions.is_down(input, Action::Fire){
+            let mouse = input.mouse_pos();
+            let aim = input.mouse_aim().then_some([mouse.x as f32, mouse.y as f32]);
+            Self::fire(gs, sprites, aim);
+        }
+
+        // any bullets shot
+        if gs.bullet_moving{
+            let targetx: f32 = sprites[TARGET].to_region[0];
+            let mut hits = [false; 3];
+            for b in 0..3 {
+                if gs.bullets[b][1] < WINDOW_HEIGHT {
+                    let [dx, dy] = gs.bullet_directions[b];
+                    gs.bullets[b][0] += dx * gs.bullet_speed * scale;
+                    gs.bullets[b][1] += dy * gs.bullet_speed * scale;
+                    let [bullet_x, bullet_y] = gs.bullets[b];
+                    // shots aimed off the sides or the bottom are gone too
+                    if !(-SPRITE_SIZE..=WINDOW_WIDTH).contains(&bullet_x) || bullet_y < -SPRITE_SIZE {
+                        gs.bullets[b][1] = WINDOW_HEIGHT;
+                        continue;
+                    }
+                    sprites[FIRST_BULLET + b].to_region = [bullet_x, bullet_y, SPRITE_SIZE, SPRITE_SIZE];
+                    if (bullet_x >= targetx-SPRITE_SIZE) && (bullet_x <= targetx + SPRITE_SIZE) && (bullet_y >= WINDOW_HEIGHT-SPRITE_SIZE-50.0) {
+                        hits[b] = true;
+                    }
+                }
+            }
+            for (b, &hit) in hits.iter().enumerate() {
+                if !hit{
+                    continue;
+                }
+                gs.score += 1;
+                gs.target_flash = TARGET_FLASH_SECONDS;
+                let [x, y, w, h] = sprites[TARGET].to_region;
+                ctx.particles.burst(Burst::Hit, [x + w/2.0, y + h/2.0], ctx.atlas.uv("bullet"), ctx.rng);
+                gs.bullet_count = gs.bullet_count.saturating_sub(1);
+                // this will reset the sprite after hitting the target
+                gs.bullets[b][1] = WINDOW_HEIGHT;
+                gs.bullet_moving = false;
+                // the first two bullets nudge the target somewhere random, the third doesn't
+                if b == 2{
+                    continue;
+                }
+                let x: f32 = ctx.rng.gen_range(0.0..10.0);
+                let sign: f32 = ctx.rng.gen_range(0.0..3.0);
+                gs.target_x = sprites[TARGET].to_region[0];
+                if (sign < 1.0) && (gs.target_x < WINDOW_WIDTH - 10.0){
+                    gs.target_x += x;
+                }else if (sign > 1.0) && (gs.target_x > 10.0){
+                    gs.target_x -= x;
+                }
+                sprites[TARGET].to_region = [gs.target_x, WINDOW_HEIGHT-SPRITE_SIZE, SPRITE_SIZE, SPRITE_SIZE];
+            }
+        }
+        Transition::None
+    }
+
+    fn draw(&self, gs: &GameState, hud: &mut Hud) {
+        hud.set("message", TextBlock::new("Target practice! Hit the target for points!", Anchor::Top).offset(0.0, 90.0).size(24.0));
+        hud.set("score", TextBlock::new(format!("Score: {}", gs.score), Anchor::TopLeft).offset(20.0, 20.0).size(24.0));
+        hud.set("timer", TextBlock::new(format!("Time left:\n{}", gs.timer.ceil() as usize), Anchor::BottomRight).offset(-20.0, -20.0).size(24.0).align(Align::Right));
+    }
+}
+
+// Space game over screen, shown when the round timer runs out
+pub struct SpaceGameOverScene {
+    // asks for initials first if the score made the high score table
+    entry: ScoreEntry,
+}
+
+impl SpaceGameOverScene {
+    pub fn new() -> SpaceGameOverScene {
+        SpaceGameOverScene { entry: ScoreEntry::new(Game::Space) }
+    }
+}
+
+impl Scene for SpaceGameOverScene {
+    fn enter(&mut self, ctx: &mut SceneContext) {
+        // hide the ship, target and bullets but keep the score around to show it
+        *ctx.sprites = sprite::hidden_sprites(ctx.gs.drop_sprite_blocks*12);
+        ctx.tweens.add(Tween::new(TweenTarget::Flash, 0.0, 0.5).from(1.0).ease(Ease::QuadOut));
+        self.entry.start(ctx, ctx.gs.score);
+    }
+
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition {
+        if self.entry.typing() {
+            self.entry.update(ctx, input);
+            Transition::None
+        }else if ctx.actions.is_down(input, Action::Confirm){
+            Transition::To(Box::new(SpaceScene))
+        }else{
+            Transition::None
+        }
+    }
+
+    fn draw(&self, gs: &GameState, hud: &mut Hud) {
+        hud.set("title", TextBlock::new("TIME'S UP!!!", Anchor::Center).offset(0.0, -80.0).size(60.0));
+        if !self.entry.typing() {
+            hud.set("menu", TextBlock::new(format!("Your score: {}\nPress c to play again\nPress esc for title screen", gs.score), Anchor::Center).offset(0.0, 50.0).align(Align::Center));
+        }
+        hud.set("scores", TextBlock::new(self.entry.text.as_str(), Anchor::Bottom).offset(0.0, -30.0).size(20.0).family(FontFamily::Monospace).align(Align::Center));
+    }
+
+    // esc skips typing initials instead of leaving
+    fn escapable(&self) -> bool {
+        !self.entry.typing()
+    }
+
+    fn wants_text(&self) -> bool {
+        self.entry.typing()
+    }
+}
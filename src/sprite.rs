@@ -0,0 +1,85 @@
+use crate::camera::GPUCamera;
+use crate::{WINDOW_WIDTH, WINDOW_HEIGHT};
+
+// sprite struct
+// to_region is where it goes on screen (x, y, w, h), from_region is where it comes from on the sprite sheet (u, v, w, h)
+// color is multiplied with the texture (r, g, b, a), WHITE draws the sprite as it is in the PNG
+// rotation is counterclockwise in radians around pivot, which is a fraction of the size (0.5, 0.5 is the middle)
+// depth decides what's drawn on top: lower depth is drawn first, sprites with the same depth keep their order
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Zeroable, bytemuck::Pod)]
+#[derive(Debug)]
+pub struct GPUSprite {
+    pub to_region: [f32;4],
+    pub from_region: [f32;4],
+    pub color: [f32;4],
+    pub rotation: f32,
+    pub depth: f32,
+    pub pivot: [f32;2],
+}
+
+// no tint, fully opaque
+pub const WHITE: [f32;4] = [1.0, 1.0, 1.0, 1.0];
+
+// depth layers, back to front
+pub const DEPTH_BACKGROUND: f32 = -10.0;
+pub const DEPTH_GAME: f32 = 0.0;
+// things flying in front of the game, like blocks tumbling off the tower
+pub const DEPTH_EFFECTS: f32 = 5.0;
+pub const DEPTH_HUD: f32 = 10.0;
+
+impl GPUSprite {
+    // width and height are 0 so that it is invisible until a screen adjusts it
+    pub fn hidden() -> GPUSprite {
+        GPUSprite {
+            to_region: [WINDOW_WIDTH, WINDOW_HEIGHT, 0.0, 0.0],
+            from_region: [0.25, 0.0, 0.25, 0.1],
+            color: WHITE,
+            rotation: 0.0,
+            depth: DEPTH_GAME,
+            pivot: [0.5, 0.5],
+        }
+    }
+
+    // the part of the sprite between two x positions, with its piece of the sprite sheet cut down to match
+    pub fn crop_x(&self, left: f32, right: f32) -> GPUSprite {
+        let [x, _, width, _] = self.to_region;
+        let start = (left - x) / width;
+        let end = (right - x) / width;
+        let mut sprite = *self;
+        sprite.to_region[0] = left;
+        sprite.to_region[2] = right - left;
+        sprite.from_region[0] += self.from_region[2] * start;
+        sprite.from_region[2] = self.from_region[2] * (end - start);
+        sprite
+    }
+}
+
+// how dark the backdrop is drawn, it's a bright region of the sprite sheet
+const BACKGROUND_TINT: [f32;4] = [0.02, 0.02, 0.05, 1.0];
+
+// the backdrop, filling everything the camera sees behind the rest of the game.
+// from_region is the "background" region of the atlas
+pub fn background(camera: &GPUCamera, from_region: [f32;4]) -> GPUSprite {
+    let [x, y] = camera.screen_pos;
+    let [width, height] = camera.screen_size;
+    GPUSprite {
+        to_region: [x, y, width, height],
+        from_region,
+        color: BACKGROUND_TINT,
+        rotation: 0.0,
+        depth: DEPTH_BACKGROUND,
+        pivot: [0.5, 0.5],
+    }
+}
+
+// fresh list of invisible sprites for a screen to fill in
+pub fn hidden_sprites(count: usize) -> Vec<GPUSprite> {
+    (0..count).map(|_| GPUSprite::hidden()).collect()
+}
+
+// put sprites in drawing order, lowest depth first.
+// the sort is stable so sprites on the same layer stay in Vec order
+pub fn sort_by_depth(sprites: &mut [GPUSprite]) {
+    sprites.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+}
@@ -0,0 +1,78 @@
+use crate::sprite::GPUSprite;
+
+// The GPU side of a list of sprites: the storage buffer the vertex shader reads from
+// and the bind group (camera + sprites) pointing at it.
+// The buffer grows (doubling) whenever there are more sprites than fit, so it never
+// disagrees with the Vec it was filled from, and only the sprites in use get uploaded.
+pub struct SpriteBatch {
+    buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    // how many sprites fit in the buffer
+    capacity: usize,
+    // how many sprites were uploaded last
+    len: usize,
+}
+
+impl SpriteBatch {
+    pub fn new(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, buffer_camera: &wgpu::Buffer, capacity: usize) -> SpriteBatch {
+        // wgpu doesn't allow binding an empty buffer
+        let capacity = capacity.max(1);
+        let buffer = Self::create_buffer(device, capacity);
+        let bind_group = Self::create_bind_group(device, layout, buffer_camera, &buffer);
+        SpriteBatch { buffer, bind_group, capacity, len: 0 }
+    }
+
+    fn create_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor{
+            label: Some("sprite batch"),
+            size: (capacity * std::mem::size_of::<GPUSprite>()) as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false
+        })
+    }
+
+    fn create_bind_group(device: &wgpu::Device, layout: &wgpu::BindGroupLayout, buffer_camera: &wgpu::Buffer, buffer_sprite: &wgpu::Buffer) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer_camera.as_entire_binding()
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: buffer_sprite.as_entire_binding()
+                }
+            ],
+        })
+    }
+
+    // copy this frame's sprites to the GPU, making the buffer bigger first if they don't fit
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, layout: &wgpu::BindGroupLayout, buffer_camera: &wgpu::Buffer, sprites: &[GPUSprite]) {
+        if sprites.len() > self.capacity {
+            while self.capacity < sprites.len() {
+                self.capacity *= 2;
+            }
+            self.buffer = Self::create_buffer(device, self.capacity);
+            self.bind_group = Self::create_bind_group(device, layout, buffer_camera, &self.buffer);
+        }
+        if !sprites.is_empty() {
+            queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(sprites));
+        }
+        self.len = sprites.len();
+    }
+
+    // bind the sprites as group 0 and draw all of them, the pipeline and texture have to be set already
+    pub fn draw<'a>(&'a self, rpass: &mut wgpu::RenderPass<'a>) {
+        if self.len == 0 {
+            return;
+        }
+        rpass.set_bind_group(0, &self.bind_group, &[]);
+        // draw two triangles per sprite, and sprites-many sprites.
+        // this uses instanced drawing, but it would also be okay
+        // to draw 6 * sprites.len() vertices and use modular arithmetic
+        // to figure out which sprite we're drawing, instead of the instance index.
+        rpass.draw(0..6, 0..(self.len as u32));
+    }
+}
@@ -0,0 +1,78 @@
+use crate::input::{Input, Key};
+
+// What the player did with a text field this update
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldEvent {
+    // pressed enter
+    Submit,
+    // pressed esc
+    Cancel,
+}
+
+// A line of text the player types into. Backspace and delete remove characters,
+// left/right/home/end move the cursor. Screens with one of these should say so
+// in Scene::wants_text so the game sends them typed characters
+pub struct TextField {
+    pub text: String,
+    // where typing goes, in characters from the start
+    pub cursor: usize,
+    pub max_len: usize,
+    // what a typed character turns into, None to ignore it (ex. letters in a number)
+    pub filter: fn(char) -> Option<char>,
+}
+
+impl TextField {
+    pub fn new(max_len: usize, filter: fn(char) -> Option<char>) -> TextField {
+        TextField { text: String::new(), cursor: 0, max_len, filter }
+    }
+
+    pub fn update(&mut self, input: &Input) -> Option<FieldEvent> {
+        for c in input.typed().chars().filter_map(self.filter) {
+            if self.text.chars().count() < self.max_len {
+                self.text.insert(self.byte_index(self.cursor), c);
+                self.cursor += 1;
+            }
+        }
+        let len = self.text.chars().count();
+        if input.is_key_pressed(Key::Back) && self.cursor > 0 {
+            self.cursor -= 1;
+            self.text.remove(self.byte_index(self.cursor));
+        }
+        if input.is_key_pressed(Key::Delete) && self.cursor < len {
+            self.text.remove(self.byte_index(self.cursor));
+        }
+        if input.is_key_pressed(Key::Left) {
+            self.cursor = self.cursor.saturating_sub(1);
+        }
+        if input.is_key_pressed(Key::Right) {
+            self.cursor = (self.cursor + 1).min(self.text.chars().count());
+        }
+        if input.is_key_pressed(Key::Home) {
+            self.cursor = 0;
+        }
+        if input.is_key_pressed(Key::End) {
+            self.cursor = self.text.chars().count();
+        }
+        if input.is_key_pressed(Key::Return) || input.is_key_pressed(Key::NumpadEnter) {
+            Some(FieldEvent::Submit)
+        } else if input.is_key_pressed(Key::Escape) {
+            Some(FieldEvent::Cancel)
+        } else {
+            None
+        }
+    }
+
+    // the text for the hud, with an underscore for the cursor and anything
+    // the IME is putting together in front of it. Both fonts have underscores
+    pub fn display(&self, preedit: &str) -> String {
+        let mut text = self.text.clone();
+        let at = self.byte_index(self.cursor);
+        text.insert(at, '_');
+        text.insert_str(at, preedit);
+        text
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text.char_indices().nth(cursor).map(|(i, _)| i).unwrap_or(self.text.len())
+    }
+}
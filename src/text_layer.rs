@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use glyphon::{Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, TextArea, TextBounds};
+
+use crate::camera::Viewport;
+use crate::hud::{FontFamily, Hud, MeasureText, TextBlock};
+use crate::WINDOW_WIDTH;
+
+// A text block turned into glyphon buffers, one per line so each line can be lined up on its own
+struct Shaped {
+    // what it was shaped from, it only gets shaped again when the text or style changes
+    block: TextBlock,
+    // each line and how wide it came out
+    lines: Vec<(Buffer, f32)>,
+    width: f32,
+    height: f32,
+}
+
+// Draws a Hud with glyphon. Keeps the shaped text around between frames
+// and remembers where every block ended up.
+pub struct TextLayer {
+    shaped: HashMap<String, Shaped>,
+    // name and top left corner of every block, in drawing order
+    placed: Vec<(String, [f32;2])>,
+}
+
+impl TextLayer {
+    pub fn new() -> TextLayer {
+        TextLayer { shaped: HashMap::new(), placed: vec![] }
+    }
+
+    // shape whatever changed since last frame and work out where every block goes.
+    // text_offset moves all of it (for sliding text around)
+    pub fn update(&mut self, font_system: &mut FontSystem, hud: &Hud, text_offset: [f32;2]) {
+        // blocks that aren't in the hud anymore
+        self.shaped.retain(|name, _| hud.get(name).is_some());
+        self.placed.clear();
+        for (name, block) in hud.blocks() {
+            let reshape = match self.shaped.get(name) {
+                Some(shaped) => shaped.block.text != block.text || shaped.block.style != block.style,
+                None => true,
+            };
+            if reshape {
+                self.shaped.insert(name.to_owned(), Self::shape(font_system, block));
+            }
+            let shaped = self.shaped.get_mut(name).unwrap();
+            // anchor and offset can change without reshaping
+            shaped.block.anchor = block.anchor;
+            shaped.block.offset = block.offset;
+            let [x, y] = block.top_left(shaped.width, shaped.height);
+            self.placed.push((name.to_owned(), [x + text_offset[0], y + text_offset[1]]));
+        }
+    }
+
+    fn shape(font_system: &mut FontSystem, block: &TextBlock) -> Shaped {
+        let style = &block.style;
+        let family = match style.family {
+            FontFamily::Serif => Family::Serif,
+            FontFamily::Monospace => Family::Monospace,
+        };
+        let mut lines = vec![];
+        for line in block.text.lines() {
+            let mut buffer = Buffer::new(font_system, Metrics::new(style.size, style.line_height()));
+            // wide enough that nothing wraps, lines only break where the text says so
+            buffer.set_size(font_system, WINDOW_WIDTH * 4.0, style.line_height());
+            buffer.set_text(font_system, line, Attrs::new().family(family), Shaping::Advanced);
+            buffer.shape_until_scroll(font_system);
+            let width = buffer.layout_runs().map(|run| run.line_w).fold(0.0, f32::max);
+            lines.push((buffer, width));
+        }
+        let width = lines.iter().map(|(_, w)| *w).fold(0.0, f32::max);
+        let height = lines.len() as f32 * style.line_height();
+        Shaped { block: block.clone(), lines, width, height }
+    }
+
+    // everything to hand to glyphon's TextRenderer::prepare, placed and scaled the same way as
+    // the sprites so they stay lined up
+    pub fn text_areas(&self, viewport: &Viewport) -> Vec<TextArea<'_>> {
+        // don't draw into the letterbox bars
+        let bounds = TextBounds {
+            left: viewport.x as i32,
+            top: viewport.y as i32,
+            right: (viewport.x + viewport.width) as i32,
+            bottom: (viewport.y + viewport.height) as i32,
+        };
+        let mut areas = vec![];
+        for (name, [x, y]) in &self.placed {
+            let shaped = &self.shaped[name];
+            let style = &shaped.block.style;
+            let [r, g, b, a] = style.color;
+            for (i, (buffer, line_width)) in shaped.lines.iter().enumerate() {
+                let line_x = x + (shaped.width - line_width) * style.align.fraction();
+                let line_y = y + i as f32 * style.line_height();
+                let (left, top) = viewport.to_window(line_x, line_y);
+                areas.push(TextArea {
+                    buffer,
+                    left,
+                    top,
+                    scale: viewport.scale(),
+                    bounds,
+                    default_color: Color::rgba(r, g, b, a),
+                });
+            }
+        }
+        areas
+    }
+}
+
+// the same size the block comes out when a TextLayer draws it
+impl MeasureText for FontSystem {
+    fn measure(&mut self, block: &TextBlock) -> [f32;2] {
+        let shaped = TextLayer::shape(self, block);
+        [shaped.width, shaped.height]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hud::{Align, Anchor};
+
+    #[test]
+    fn menu_hit_box_is_where_the_text_is_placed() {
+        let mut font_system = FontSystem::new();
+        let block = TextBlock::new("Block game", Anchor::Center).offset(0.0, 42.0).align(Align::Center);
+        let text_offset = [-30.0, 5.0];
+        let mut hud = Hud::default();
+        hud.set("menu_0", block.clone());
+        let mut layer = TextLayer::new();
+        layer.update(&mut font_system, &hud, text_offset);
+        let shaped = &layer.shaped["menu_0"];
+        let [x, y] = layer.placed[0].1;
+        assert_eq!(block.layout_bounds(&mut font_system, text_offset), [x, y, shaped.width, shaped.height]);
+    }
+}
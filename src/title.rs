@@ -0,0 +1,116 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::actions::Action;
+use crate::block_game::BlockSetupScene;
+use crate::controls_menu::ControlsScene;
+use crate::game_state;
+use crate::hud::{Align, Anchor, Hud, TextBlock};
+use crate::input::Input;
+use crate::menu::Menu;
+use crate::scene::{Scene, SceneContext, Transition};
+use crate::space_game::SpaceScene;
+use crate::sprite;
+use crate::text_field::{FieldEvent, TextField};
+use crate::tween::{Ease, Tween, TweenTarget};
+
+// TITLE SCREEN - pick which game to play
+pub struct TitleScene {
+    menu: Menu,
+}
+
+// what's on the menu and which action clicking it stands for
+const TITLE_ITEMS: [(&str, Action); 4] = [
+    ("Press a for Falling Blocks", Action::PlayBlocks),
+    ("Press b for Space Blocks", Action::PlaySpace),
+    ("Press k for Controls", Action::Controls),
+    ("Press s to type a seed", Action::Seed),
+];
+
+impl TitleScene {
+    pub fn new() -> TitleScene {
+        TitleScene { menu: Menu::new("menu", &TITLE_ITEMS.map(|(text, _)| text)).offset(50.0) }
+    }
+}
+
+impl Scene for TitleScene {
+    fn enter(&mut self, ctx: &mut SceneContext) {
+        *ctx.gs = game_state::init_game_state();
+        // number of max dropped per row * 12 is the maximum number of sprites needed for the game.
+        // MAKE BUFFER BIGGER FOR SPACE GAME
+        *ctx.sprites = sprite::hidden_sprites(ctx.gs.drop_sprite_blocks*36);
+        // title drops in from above and settles with a wobble
+        ctx.tweens.add(Tween::new(TweenTarget::TextY, 0.0, 0.8).from(-200.0).ease(Ease::ElasticOut));
+    }
+
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition {
+        let clicked = self.menu.update(ctx, input).map(|i| TITLE_ITEMS[i].1);
+        let chosen = |action| clicked == Some(action) || ctx.actions.is_down(input, action);
+        if chosen(Action::PlayBlocks){
+            Transition::To(Box::new(BlockSetupScene::new()))
+        }else if chosen(Action::PlaySpace){
+            Transition::To(Box::new(SpaceScene))
+        }else if chosen(Action::Controls){
+            Transition::To(Box::<ControlsScene>::default())
+        }else if chosen(Action::Seed){
+            Transition::To(Box::new(SeedScene::new()))
+        }else{
+            Transition::None
+        }
+    }
+
+    fn draw(&self, _gs: &game_state::GameState, hud: &mut Hud) {
+        hud.set("title", TextBlock::new("Block Games!!!", Anchor::Center).offset(0.0, -60.0).size(60.0));
+        self.menu.draw(hud);
+    }
+}
+
+// longest seed that always fits in a u64
+const SEED_DIGITS: usize = 19;
+
+// SEED ENTRY - type a number and the next game plays out the same as anyone else's with that number
+pub struct SeedScene {
+    field: TextField,
+    // what's been typed so far, kept from the last update for draw
+    text: String,
+}
+
+impl SeedScene {
+    pub fn new() -> SeedScene {
+        let field = TextField::new(SEED_DIGITS, |c| c.is_ascii_digit().then_some(c));
+        let text = field.display("");
+        SeedScene { field, text }
+    }
+}
+
+impl Scene for SeedScene {
+    fn update(&mut self, ctx: &mut SceneContext, input: &Input) -> Transition {
+        let event = self.field.update(input);
+        self.text = self.field.display(input.preedit());
+        match event {
+            Some(FieldEvent::Submit) => {
+                if let Ok(seed) = self.field.text.parse::<u64>() {
+                    log::info!("seed: {}", seed);
+                    *ctx.rng = StdRng::seed_from_u64(seed);
+                }
+                Transition::To(Box::new(TitleScene::new()))
+            }
+            Some(FieldEvent::Cancel) => Transition::To(Box::new(TitleScene::new())),
+            None => Transition::None,
+        }
+    }
+
+    fn draw(&self, _gs: &game_state::GameState, hud: &mut Hud) {
+        hud.set("title", TextBlock::new("Seed", Anchor::Center).offset(0.0, -80.0).size(60.0));
+        hud.set("menu", TextBlock::new(format!("{}\nType a number and press enter\nThe next game plays out the same for everyone with it", self.text), Anchor::Center).offset(0.0, 50.0).align(Align::Center));
+    }
+
+    // esc is handled by the text field
+    fn escapable(&self) -> bool {
+        false
+    }
+
+    fn wants_text(&self) -> bool {
+        true
+    }
+}
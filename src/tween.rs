@@ -0,0 +1,347 @@
+use crate::camera::GPUCamera;
+use crate::game_state::GameState;
+use crate::sprite::GPUSprite;
+
+// Easing curves, they take how far along the tween is (0 to 1) and give back how far along the value is.
+// In speeds up from the start, Out slows down into the end, InOut does both.
+// See https://easings.net for what they look like.
+// The games only use some of these (and of the fields and targets below) so far, the tests cover the rest
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ease {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    // overshoots and wobbles
+    ElasticIn,
+    ElasticOut,
+    // hits the end and bounces off it a few times
+    BounceIn,
+    BounceOut,
+}
+
+impl Ease {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Ease::Linear => t,
+            Ease::QuadIn => t * t,
+            Ease::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Ease::QuadInOut => if t < 0.5 { 2.0 * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0 },
+            Ease::CubicIn => t * t * t,
+            Ease::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Ease::CubicInOut => if t < 0.5 { 4.0 * t * t * t } else { 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0 },
+            Ease::ElasticIn => 1.0 - Ease::ElasticOut.apply(1.0 - t),
+            Ease::ElasticOut => {
+                if t == 0.0 || t == 1.0 {
+                    return t;
+                }
+                let c4 = 2.0 * std::f32::consts::PI / 3.0;
+                2.0_f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * c4).sin() + 1.0
+            }
+            Ease::BounceIn => 1.0 - Ease::BounceOut.apply(1.0 - t),
+            Ease::BounceOut => {
+                let n1 = 7.5625;
+                let d1 = 2.75;
+                if t < 1.0 / d1 {
+                    n1 * t * t
+                } else if t < 2.0 / d1 {
+                    let t = t - 1.5 / d1;
+                    n1 * t * t + 0.75
+                } else if t < 2.5 / d1 {
+                    let t = t - 2.25 / d1;
+                    n1 * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / d1;
+                    n1 * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+// Which number on a sprite a tween changes
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpriteField {
+    // to_region
+    X,
+    Y,
+    Width,
+    Height,
+    // from_region
+    U,
+    V,
+    // color
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Rotation,
+    Depth,
+    PivotX,
+    PivotY,
+}
+
+// What a tween changes
+#[cfg_attr(not(test), allow(dead_code))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TweenTarget {
+    // a field of the sprite at this index in the sprite list
+    Sprite(usize, SpriteField),
+    CameraX,
+    CameraY,
+    // how far the screen's text is moved from where it normally goes, in pixels with y going down like the window
+    TextX,
+    TextY,
+    // how white the screen is flashed, 0 to 1
+    Flash,
+}
+
+// called with the game state when a tween is done
+type OnDone = Box<dyn FnOnce(&mut GameState)>;
+
+// Animates one value to `to` over `duration` seconds. Build one with Tween::new and the
+// methods below, then hand it to Tweens::add.
+pub struct Tween {
+    target: TweenTarget,
+    // None starts from wherever the value is when the tween starts
+    from: Option<f32>,
+    to: f32,
+    duration: f32,
+    delay: f32,
+    ease: Ease,
+    on_done: Option<OnDone>,
+    // starts once this one is done
+    next: Option<Box<Tween>>,
+}
+
+impl Tween {
+    pub fn new(target: TweenTarget, to: f32, duration: f32) -> Tween {
+        Tween { target, from: None, to, duration, delay: 0.0, ease: Ease::Linear, on_done: None, next: None }
+    }
+
+    pub fn ease(mut self, ease: Ease) -> Tween {
+        self.ease = ease;
+        self
+    }
+
+    // jump the value here when the tween starts instead of starting where it is
+    pub fn from(mut self, from: f32) -> Tween {
+        self.from = Some(from);
+        self
+    }
+
+    // wait this many seconds before starting
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn delay(mut self, seconds: f32) -> Tween {
+        self.delay = seconds;
+        self
+    }
+
+    // called with the game state once this tween is done (before the next one in the chain starts)
+    pub fn on_done(mut self, callback: impl FnOnce(&mut GameState) + 'static) -> Tween {
+        self.on_done = Some(Box::new(callback));
+        self
+    }
+
+    // run next after this one (and after anything already chained on to it)
+    pub fn then(mut self, next: Tween) -> Tween {
+        match self.next {
+            Some(chained) => self.next = Some(Box::new(chained.then(next))),
+            None => self.next = Some(Box::new(next)),
+        }
+        self
+    }
+}
+
+struct Running {
+    tween: Tween,
+    // value it started at, set once the delay is over
+    start: Option<f32>,
+    elapsed: f32,
+}
+
+// Everything a tween is allowed to change
+pub struct TweenTargets<'a> {
+    pub sprites: &'a mut [GPUSprite],
+    pub camera: &'a mut GPUCamera,
+    pub text_offset: &'a mut [f32;2],
+    pub flash: &'a mut f32,
+}
+
+impl TweenTargets<'_> {
+    // None if the sprite isn't there anymore
+    fn value(&mut self, target: TweenTarget) -> Option<&mut f32> {
+        Some(match target {
+            TweenTarget::Sprite(i, field) => {
+                let sprite = self.sprites.get_mut(i)?;
+                match field {
+                    SpriteField::X => &mut sprite.to_region[0],
+                    SpriteField::Y => &mut sprite.to_region[1],
+                    SpriteField::Width => &mut sprite.to_region[2],
+                    SpriteField::Height => &mut sprite.to_region[3],
+                    SpriteField::U => &mut sprite.from_region[0],
+                    SpriteField::V => &mut sprite.from_region[1],
+                    SpriteField::Red => &mut sprite.color[0],
+                    SpriteField::Green => &mut sprite.color[1],
+                    SpriteField::Blue => &mut sprite.color[2],
+                    SpriteField::Alpha => &mut sprite.color[3],
+                    SpriteField::Rotation => &mut sprite.rotation,
+                    SpriteField::Depth => &mut sprite.depth,
+                    SpriteField::PivotX => &mut sprite.pivot[0],
+                    SpriteField::PivotY => &mut sprite.pivot[1],
+                }
+            }
+            TweenTarget::CameraX => &mut self.camera.screen_pos[0],
+            TweenTarget::CameraY => &mut self.camera.screen_pos[1],
+            TweenTarget::TextX => &mut self.text_offset[0],
+            TweenTarget::TextY => &mut self.text_offset[1],
+            TweenTarget::Flash => self.flash,
+        })
+    }
+}
+
+// All the tweens that are running
+#[derive(Default)]
+pub struct Tweens {
+    running: Vec<Running>,
+}
+
+impl Tweens {
+    pub fn add(&mut self, tween: Tween) {
+        self.running.push(Running { tween, start: None, elapsed: 0.0 });
+    }
+
+    // stop everything without finishing it, callbacks don't run
+    pub fn clear(&mut self) {
+        self.running.clear();
+    }
+
+    // is anything still changing this target
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn is_running(&self, target: TweenTarget) -> bool {
+        self.running.iter().any(|r| r.tween.target == target)
+    }
+
+    // move every tween along by dt seconds, finished ones call back and start whatever is chained after them
+    pub fn update(&mut self, dt: f32, targets: &mut TweenTargets, gs: &mut GameState) {
+        let mut finished = vec![];
+        for (i, running) in self.running.iter_mut().enumerate() {
+            running.elapsed += dt;
+            let tween = &running.tween;
+            if running.elapsed < tween.delay {
+                continue;
+            }
+            let Some(value) = targets.value(tween.target) else {
+                // what it was changing is gone, nothing left to do
+                finished.push(i);
+                continue;
+            };
+            let start = *running.start.get_or_insert(tween.from.unwrap_or(*value));
+            let t = if tween.duration > 0.0 { (running.elapsed - tween.delay) / tween.duration } else { 1.0 };
+            *value = start + (tween.to - start) * tween.ease.apply(t);
+            if t >= 1.0 {
+                finished.push(i);
+            }
+        }
+        // back to front so the indices stay good
+        for i in finished.into_iter().rev() {
+            let tween = self.running.remove(i).tween;
+            if let Some(on_done) = tween.on_done {
+                on_done(gs);
+            }
+            if let Some(next) = tween.next {
+                self.add(*next);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state;
+
+    const ALL_EASES: [Ease; 11] = [
+        Ease::Linear, Ease::QuadIn, Ease::QuadOut, Ease::QuadInOut, Ease::CubicIn, Ease::CubicOut,
+        Ease::CubicInOut, Ease::ElasticIn, Ease::ElasticOut, Ease::BounceIn, Ease::BounceOut,
+    ];
+
+    const ALL_FIELDS: [SpriteField; 14] = [
+        SpriteField::X, SpriteField::Y, SpriteField::Width, SpriteField::Height, SpriteField::U, SpriteField::V,
+        SpriteField::Red, SpriteField::Green, SpriteField::Blue, SpriteField::Alpha,
+        SpriteField::Rotation, SpriteField::Depth, SpriteField::PivotX, SpriteField::PivotY,
+    ];
+
+    // run tweens for a while on one sprite and the camera
+    fn run(tweens: &mut Tweens, sprites: &mut [GPUSprite], camera: &mut GPUCamera, seconds: f32) {
+        let mut text_offset = [0.0, 0.0];
+        let mut flash = 0.0;
+        let mut gs = game_state::init_game_state();
+        let mut targets = TweenTargets { sprites, camera, text_offset: &mut text_offset, flash: &mut flash };
+        let dt = 0.02;
+        for _ in 0..(seconds / dt).round() as usize {
+            tweens.update(dt, &mut targets, &mut gs);
+        }
+    }
+
+    #[test]
+    fn every_ease_starts_at_0_and_ends_at_1() {
+        for ease in ALL_EASES {
+            assert!(ease.apply(0.0).abs() < 1e-5, "{:?} starts at {}", ease, ease.apply(0.0));
+            assert!((ease.apply(1.0) - 1.0).abs() < 1e-5, "{:?} ends at {}", ease, ease.apply(1.0));
+        }
+    }
+
+    #[test]
+    fn tweens_every_sprite_field() {
+        // each field ends up as a different number of the sprite
+        let mut changed = vec![];
+        for field in ALL_FIELDS {
+            let mut sprites = [GPUSprite::hidden()];
+            let mut tweens = Tweens::default();
+            tweens.add(Tween::new(TweenTarget::Sprite(0, field), 7.0, 0.1));
+            run(&mut tweens, &mut sprites, &mut GPUCamera::default(), 0.2);
+            let hidden = [GPUSprite::hidden()];
+            let before: &[f32] = bytemuck::cast_slice(&hidden);
+            let after: &[f32] = bytemuck::cast_slice(&sprites);
+            let diff: Vec<usize> = (0..after.len()).filter(|&i| after[i] != before[i]).collect();
+            assert_eq!(diff.len(), 1, "{:?}", field);
+            assert_eq!(after[diff[0]], 7.0);
+            changed.push(diff[0]);
+        }
+        changed.sort();
+        changed.dedup();
+        assert_eq!(changed.len(), ALL_FIELDS.len());
+    }
+
+    #[test]
+    fn tween_on_camera_x_moves_the_camera() {
+        let mut camera = GPUCamera::default();
+        let mut tweens = Tweens::default();
+        tweens.add(Tween::new(TweenTarget::CameraX, 100.0, 0.5).ease(Ease::CubicInOut));
+        tweens.add(Tween::new(TweenTarget::CameraY, -50.0, 0.5).ease(Ease::QuadIn));
+        run(&mut tweens, &mut [], &mut camera, 0.25);
+        assert!(camera.screen_pos[0] > 0.0 && camera.screen_pos[0] < 100.0);
+        run(&mut tweens, &mut [], &mut camera, 0.3);
+        assert_eq!(camera.screen_pos, [100.0, -50.0]);
+        assert!(!tweens.is_running(TweenTarget::CameraX));
+    }
+
+    #[test]
+    fn delay_holds_a_tween_back() {
+        let mut camera = GPUCamera::default();
+        let mut tweens = Tweens::default();
+        tweens.add(Tween::new(TweenTarget::CameraX, 10.0, 0.1).delay(0.5));
+        run(&mut tweens, &mut [], &mut camera, 0.4);
+        assert_eq!(camera.screen_pos[0], 0.0);
+        assert!(tweens.is_running(TweenTarget::CameraX));
+        run(&mut tweens, &mut [], &mut camera, 0.3);
+        assert_eq!(camera.screen_pos[0], 10.0);
+    }
+}